@@ -0,0 +1,179 @@
+//! The projection as it was before the row kernels: every cell looks up its
+//! four neighbors through the boundaries on every sweep. Kept to show what
+//! the kernels in `stencil` gain, it matches [`Simulation::project`] within
+//! rounding.
+
+use std::mem;
+
+use glam::{IVec2, UVec2};
+use mac_grid_fluid::{Boundary, CellGrid, Float, PressureSolver, Simulation};
+
+enum Neighbor {
+    Fluid(UVec2),
+    Solid,
+    Open,
+}
+
+fn neighbor(simulation: &Simulation, normalized: IVec2) -> Neighbor {
+    let wrapped = IVec2::select(
+        simulation.boundaries.wrap(),
+        normalized.rem_euclid(simulation.dimensions.as_ivec2()),
+        normalized,
+    );
+    if simulation.solids.contains(wrapped) {
+        let cell = wrapped.as_uvec2();
+        if simulation.solids[cell] {
+            Neighbor::Solid
+        } else {
+            Neighbor::Fluid(cell)
+        }
+    } else {
+        match simulation.boundaries.beyond(wrapped, simulation.dimensions) {
+            Boundary::Wall | Boundary::Periodic => Neighbor::Solid,
+            Boundary::Open => Neighbor::Open,
+        }
+    }
+}
+
+fn neighbor_pressures(
+    simulation: &Simulation,
+    pressures: &CellGrid<Float>,
+    cell: UVec2,
+) -> (Float, u32) {
+    let mut sum = 0.;
+    let mut count = 0;
+    for offset in [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y] {
+        match neighbor(simulation, cell.as_ivec2() + offset) {
+            Neighbor::Fluid(neighbor) => {
+                sum += pressures[neighbor];
+                count += 1;
+            }
+            Neighbor::Open => count += 1,
+            Neighbor::Solid => {}
+        }
+    }
+    (sum, count)
+}
+
+fn solve_pressure(
+    simulation: &Simulation,
+    constants: &CellGrid<Float>,
+    pressures: &CellGrid<Float>,
+    cell: UVec2,
+) -> Float {
+    if simulation.solids[cell] {
+        return 0.;
+    }
+    let (sum, count) = neighbor_pressures(simulation, pressures, cell);
+    if count == 0 {
+        return 0.;
+    }
+    (sum - constants[cell]) / count as Float
+}
+
+fn pressure_residual(simulation: &Simulation, constants: &CellGrid<Float>) -> Float {
+    let (sum, count) = simulation
+        .pressures
+        .indices()
+        .filter(|&cell| !simulation.solids[cell])
+        .fold((0., 0), |(sum, count), cell| {
+            let (neighbors, neighbor_count) =
+                neighbor_pressures(simulation, &simulation.pressures, cell);
+            let residual =
+                neighbors - neighbor_count as Float * simulation.pressures[cell] - constants[cell];
+            (sum + residual * residual, count + 1)
+        });
+    if count == 0 {
+        return 0.;
+    }
+    (sum / count as Float).sqrt()
+}
+
+fn face_pressure(simulation: &Simulation, normalized: IVec2) -> Option<Float> {
+    match neighbor(simulation, normalized) {
+        Neighbor::Fluid(cell) => Some(simulation.pressures[cell]),
+        Neighbor::Solid => None,
+        Neighbor::Open => Some(0.),
+    }
+}
+
+/// Same as [`Simulation::project`], one cell at a time.
+pub fn project(simulation: &mut Simulation) -> Float {
+    let mut constants = CellGrid::new(simulation.dimensions, 0.);
+    constants.fill_with(|cell| {
+        let outflow = if simulation.solids[cell] {
+            0.
+        } else {
+            simulation.velocities_x[cell + UVec2::X] - simulation.velocities_x[cell]
+                + simulation.velocities_y[cell + UVec2::Y]
+                - simulation.velocities_y[cell]
+        };
+        simulation.cell_size / simulation.time_step * outflow
+    });
+    let mut back = simulation.pressures.clone();
+
+    match simulation.solver.method {
+        PressureSolver::Jacobi => {
+            for _ in 0..simulation.solver.iterations {
+                back.fill_with(|cell| {
+                    solve_pressure(simulation, &constants, &simulation.pressures, cell)
+                });
+                mem::swap(&mut simulation.pressures, &mut back);
+            }
+        }
+        PressureSolver::GaussSeidel => {
+            for _ in 0..simulation.solver.iterations {
+                for cell in back.indices() {
+                    back[cell] = solve_pressure(simulation, &constants, &back, cell);
+                }
+            }
+            mem::swap(&mut simulation.pressures, &mut back);
+        }
+        PressureSolver::RedBlack => {
+            for _ in 0..simulation.solver.iterations {
+                for parity in [0, 1] {
+                    back.fill_with(|cell| {
+                        if (cell.x + cell.y) % 2 == parity {
+                            solve_pressure(simulation, &constants, &simulation.pressures, cell)
+                        } else {
+                            simulation.pressures[cell]
+                        }
+                    });
+                    mem::swap(&mut simulation.pressures, &mut back);
+                }
+            }
+        }
+    }
+    let pressure_residual = pressure_residual(simulation, &constants);
+
+    let mut velocities_x = simulation.velocities_x.clone();
+    velocities_x.fill_with(|face| {
+        let right = face.as_ivec2();
+        let velocity = simulation.velocities_x[face];
+        let (Some(pressure_left), Some(pressure_right)) = (
+            face_pressure(simulation, right - IVec2::X),
+            face_pressure(simulation, right),
+        ) else {
+            return velocity;
+        };
+        let pressure_gradient = (pressure_right - pressure_left) / simulation.cell_size;
+        velocity - simulation.time_step * pressure_gradient
+    });
+    let mut velocities_y = simulation.velocities_y.clone();
+    velocities_y.fill_with(|face| {
+        let top = face.as_ivec2();
+        let velocity = simulation.velocities_y[face];
+        let (Some(pressure_bottom), Some(pressure_top)) = (
+            face_pressure(simulation, top - IVec2::Y),
+            face_pressure(simulation, top),
+        ) else {
+            return velocity;
+        };
+        let pressure_gradient = (pressure_top - pressure_bottom) / simulation.cell_size;
+        velocity - simulation.time_step * pressure_gradient
+    });
+    simulation.velocities_x = velocities_x;
+    simulation.velocities_y = velocities_y;
+
+    pressure_residual
+}
@@ -0,0 +1,117 @@
+mod per_cell;
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use glam::uvec2;
+use mac_grid_fluid::{float::from_uvec2, Float, PressureSolver, Simulation, Vector};
+
+const SIZES: [u32; 3] = [64, 256, 1024];
+const SOLVERS: [PressureSolver; 3] = [
+    PressureSolver::Jacobi,
+    PressureSolver::GaussSeidel,
+    PressureSolver::RedBlack,
+];
+
+/// A square simulation pushed in the middle and advected once, so that the
+/// pressure solve has divergence to remove.
+fn simulation(size: u32, method: PressureSolver) -> Simulation {
+    let mut simulation = Simulation::new(uvec2(size, size), 1., 0.5).unwrap();
+    simulation.solver.method = method;
+    let centre = from_uvec2(simulation.dimensions) * 0.5;
+    simulation.interact(centre, Vector::X, size as Float * 0.1);
+    simulation.advect();
+    simulation
+}
+
+fn step(c: &mut Criterion) {
+    let mut group = c.benchmark_group("step");
+    group.sample_size(10);
+    for size in SIZES {
+        for method in SOLVERS {
+            let simulation = simulation(size, method);
+            group.throughput(Throughput::Elements(size as u64 * size as u64));
+            group.bench_with_input(
+                BenchmarkId::new(format!("{method:?}"), size),
+                &simulation,
+                |b, simulation| {
+                    b.iter_batched_ref(
+                        || simulation.clone(),
+                        |simulation| simulation.step(),
+                        BatchSize::LargeInput,
+                    )
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+fn advect(c: &mut Criterion) {
+    let mut group = c.benchmark_group("advect");
+    group.sample_size(10);
+    for size in SIZES {
+        let simulation = simulation(size, PressureSolver::default());
+        group.throughput(Throughput::Elements(size as u64 * size as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(size),
+            &simulation,
+            |b, simulation| {
+                b.iter_batched_ref(
+                    || simulation.clone(),
+                    |simulation| simulation.advect(),
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+    }
+    group.finish();
+}
+
+fn project(c: &mut Criterion) {
+    let mut group = c.benchmark_group("project");
+    group.sample_size(10);
+    for size in SIZES {
+        for method in SOLVERS {
+            let simulation = simulation(size, method);
+            group.throughput(Throughput::Elements(size as u64 * size as u64));
+            group.bench_with_input(
+                BenchmarkId::new(format!("{method:?}"), size),
+                &simulation,
+                |b, simulation| {
+                    b.iter_batched_ref(
+                        || simulation.clone(),
+                        |simulation| simulation.project(),
+                        BatchSize::LargeInput,
+                    )
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+/// The projection before the row kernels, to compare with `project`.
+fn project_per_cell(c: &mut Criterion) {
+    let mut group = c.benchmark_group("project_per_cell");
+    group.sample_size(10);
+    for size in SIZES {
+        for method in SOLVERS {
+            let simulation = simulation(size, method);
+            group.throughput(Throughput::Elements(size as u64 * size as u64));
+            group.bench_with_input(
+                BenchmarkId::new(format!("{method:?}"), size),
+                &simulation,
+                |b, simulation| {
+                    b.iter_batched_ref(
+                        || simulation.clone(),
+                        per_cell::project,
+                        BatchSize::LargeInput,
+                    )
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, step, advect, project, project_per_cell);
+criterion_main!(benches);
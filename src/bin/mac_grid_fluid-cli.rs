@@ -0,0 +1,323 @@
+use std::{
+    error::Error,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    process::exit,
+};
+
+use clap::{Parser, ValueEnum};
+use glam::uvec2;
+use mac_grid_fluid::{
+    float::from_uvec2,
+    input::{Controls, Input, Replay},
+    npy,
+    recorder::Recorder,
+    scene::{self, ViewerSettings},
+    vtk::VtkSeries,
+    Float, Scene, Simulation, Vector,
+};
+use tracing::{error, info, subscriber::set_global_default, warn};
+use tracing_subscriber::FmtSubscriber;
+
+/// Runs a simulation without graphics and writes snapshots and stats to disk.
+#[derive(Parser)]
+#[command(version)]
+struct Args {
+    /// Scene file to set up the simulation from, instead of the options below.
+    #[arg(
+        long,
+        conflicts_with_all = ["width", "height", "cell_size", "time_step", "scenario"],
+    )]
+    scene: Option<PathBuf>,
+
+    /// Binary snapshot to continue from, e.g. one saved in the viewer.
+    #[arg(
+        long,
+        conflicts_with_all = ["scene", "width", "height", "cell_size", "time_step", "scenario"],
+    )]
+    load: Option<PathBuf>,
+
+    /// Save a binary snapshot of the final state here.
+    #[arg(long)]
+    save: Option<PathBuf>,
+
+    /// Number of cells along x.
+    #[arg(long, default_value_t = 60, value_parser = clap::value_parser!(u32).range(1..))]
+    width: u32,
+
+    /// Number of cells along y.
+    #[arg(long, default_value_t = 30, value_parser = clap::value_parser!(u32).range(1..))]
+    height: u32,
+
+    #[arg(long, default_value_t = 20.)]
+    cell_size: Float,
+
+    #[arg(long, default_value_t = 0.5)]
+    time_step: Float,
+
+    #[arg(long, default_value_t = 100)]
+    steps: u32,
+
+    #[arg(long, value_enum, default_value_t = Scenario::Uniform)]
+    scenario: Scenario,
+
+    /// Write a snapshot every this many steps, zero disables snapshots.
+    #[arg(long, default_value_t = 10)]
+    snapshot_interval: u32,
+
+    /// Also write every snapshot as ParaView `.vti` files, indexed by
+    /// `simulation.pvd`.
+    #[arg(long)]
+    vtk: bool,
+
+    /// Also write the fields of every snapshot for NumPy.
+    #[arg(long, value_enum)]
+    numpy: Option<NumpyFormat>,
+
+    /// Record energy, enstrophy and divergence of every step to this file, as
+    /// JSON lines for `.jsonl` files and CSV otherwise.
+    #[arg(long)]
+    record: Option<PathBuf>,
+
+    /// Play the viewer inputs recorded in this file instead of a fixed number
+    /// of steps, starting from the same scene or snapshot as the recording.
+    #[arg(long, conflicts_with_all = ["steps", "scenario"])]
+    replay: Option<PathBuf>,
+
+    /// Sum in a fixed order, so that runs are bitwise reproducible with any
+    /// number of threads.
+    #[arg(long)]
+    deterministic: bool,
+
+    /// Directory for `stats.csv` and the snapshots.
+    #[arg(long, default_value = "output")]
+    output: PathBuf,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum NumpyFormat {
+    /// One `{field}_{step}.npy` per field.
+    Npy,
+    /// One `fields_{step}.npz` with all fields.
+    Npz,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Scenario {
+    /// Uniform diagonal flow, as in the viewer.
+    Uniform,
+    /// Fluid at rest.
+    Still,
+    /// Fluid at rest, pushed to the right near the left wall every step.
+    Jet,
+    /// Solid body rotation around the centre of the domain.
+    Vortex,
+}
+
+impl Scenario {
+    fn setup(self, simulation: &mut Simulation) {
+        match self {
+            Scenario::Uniform => {}
+            Scenario::Still | Scenario::Jet => {
+                simulation.velocities_x.fill(0.);
+                simulation.velocities_y.fill(0.);
+            }
+            Scenario::Vortex => {
+                let centre = from_uvec2(simulation.dimensions) * 0.5;
+                let scale = centre.min_element();
+                for face in simulation.velocities_x.indices() {
+                    let offset = simulation.velocities_x.position(face) - centre;
+                    simulation.velocities_x[face] = -offset.y / scale;
+                }
+                for face in simulation.velocities_y.indices() {
+                    let offset = simulation.velocities_y.position(face) - centre;
+                    simulation.velocities_y[face] = offset.x / scale;
+                }
+            }
+        }
+    }
+
+    fn drive(self, simulation: &mut Simulation) {
+        if let Scenario::Jet = self {
+            let position =
+                Vector::new(2., 0.5 * simulation.dimensions.y as Float) * simulation.cell_size;
+            simulation.interact(position, Vector::X, simulation.cell_size * 3.);
+        }
+    }
+}
+
+fn main() {
+    set_global_default(FmtSubscriber::default()).unwrap();
+
+    if let Err(err) = run(Args::parse()) {
+        error!("{err}");
+        exit(1);
+    }
+}
+
+fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    let (mut simulation, scenario, viewer) = match (&args.scene, &args.load) {
+        (Some(path), _) => {
+            let scene = Scene::load(path)?;
+            (scene.simulation()?, None, scene.viewer)
+        }
+        (_, Some(path)) => (
+            Simulation::load(BufReader::new(File::open(path)?))?,
+            None,
+            ViewerSettings::default(),
+        ),
+        (None, None) => {
+            let scene = Scene {
+                grid: scene::GridSettings {
+                    dimensions: uvec2(args.width, args.height),
+                    cell_size: args.cell_size,
+                },
+                solver: scene::SolverSettings {
+                    time_step: args.time_step,
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            let mut simulation = scene.simulation()?;
+            args.scenario.setup(&mut simulation);
+            (simulation, Some(args.scenario), ViewerSettings::default())
+        }
+    };
+
+    simulation.deterministic |= args.deterministic || args.replay.is_some();
+
+    let mut replay = match &args.replay {
+        Some(path) => {
+            let replay = Replay::load(path)?;
+            if replay.initial_hash != simulation.state_hash() {
+                warn!("the replay was recorded from a different initial state");
+            }
+            Some((replay, Controls::new(viewer.interaction_radius), 0))
+        }
+        None => None,
+    };
+
+    fs::create_dir_all(&args.output)?;
+    let mut stats = BufWriter::new(File::create(args.output.join("stats.csv"))?);
+    writeln!(stats, "step,time,max_velocity,mean_pressure")?;
+
+    let mut vtk = args.vtk.then(|| VtkSeries::new(&args.output, "simulation"));
+    let mut recorder = args.record.as_ref().map(Recorder::create).transpose()?;
+
+    for step in 0.. {
+        if step > 0 {
+            match &mut replay {
+                Some((replay, controls, frame)) => {
+                    if !replay_step(replay, controls, frame, &mut simulation)? {
+                        break;
+                    }
+                }
+                None if step <= args.steps => {
+                    if let Some(scenario) = scenario {
+                        scenario.drive(&mut simulation);
+                    }
+                    simulation.step();
+                }
+                None => break,
+            }
+        }
+
+        let max_velocity = simulation
+            .velocities_x
+            .iter()
+            .chain(simulation.velocities_y.iter())
+            .fold(0., |max: Float, velocity| max.max(velocity.abs()));
+        let mean_pressure =
+            simulation.pressures.iter().sum::<Float>() / simulation.pressures.len() as Float;
+        writeln!(
+            stats,
+            "{step},{},{max_velocity},{mean_pressure}",
+            step as Float * simulation.time_step,
+        )?;
+        if let Some(recorder) = &mut recorder {
+            recorder.record(&simulation, step)?;
+        }
+
+        if args.snapshot_interval != 0 && step % args.snapshot_interval == 0 {
+            let path = args.output.join(format!("snapshot_{step:06}.csv"));
+            write_snapshot(&simulation, &path)?;
+            info!(step, path = %path.display(), "snapshot");
+
+            if let Some(vtk) = &mut vtk {
+                vtk.write_step(&simulation, step, step as Float * simulation.time_step)?;
+            }
+
+            match args.numpy {
+                Some(NumpyFormat::Npy) => {
+                    for (name, grid) in npy::fields(&simulation) {
+                        let path = args.output.join(format!("{name}_{step:06}.npy"));
+                        npy::write_npy(BufWriter::new(File::create(path)?), &grid)?;
+                    }
+                }
+                Some(NumpyFormat::Npz) => {
+                    let path = args.output.join(format!("fields_{step:06}.npz"));
+                    let fields = npy::fields(&simulation);
+                    npy::write_npz(BufWriter::new(File::create(path)?), &fields)?;
+                }
+                None => {}
+            }
+        }
+    }
+
+    stats.flush()?;
+    if let Some(recorder) = &mut recorder {
+        recorder.flush()?;
+    }
+    info!(hash = %format!("{:016x}", simulation.state_hash()), "final state");
+
+    if let Some(path) = &args.save {
+        simulation.save(BufWriter::new(File::create(path)?))?;
+        info!(path = %path.display(), "saved final state");
+    }
+
+    Ok(())
+}
+
+/// Plays frames of the replay until one of them steps the simulation, false
+/// once the inputs run out before that.
+fn replay_step(
+    replay: &mut Replay,
+    controls: &mut Controls,
+    frame: &mut u64,
+    simulation: &mut Simulation,
+) -> Result<bool, Box<dyn Error>> {
+    while !replay.is_finished() {
+        let mut step = false;
+        for input in replay.frame(*frame) {
+            controls.apply(&input);
+            match input {
+                Input::Step => step = true,
+                Input::Load => return Err("the replay loads a snapshot of the viewer".into()),
+                Input::Quit => return Ok(false),
+                _ => {}
+            }
+        }
+        *frame += 1;
+        if controls.advance(simulation, step) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// One line per cell with its centre, pressure, interpolated velocity and dye.
+fn write_snapshot(simulation: &Simulation, path: &Path) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    writeln!(file, "x,y,pressure,velocity_x,velocity_y,dye")?;
+    for (cell, pressure) in simulation.pressures.enumerate() {
+        let position = simulation.pressures.position(cell) * simulation.cell_size;
+        let velocity = simulation.interpolate_velocity(position);
+        writeln!(
+            file,
+            "{},{},{pressure},{},{},{}",
+            position.x, position.y, velocity.x, velocity.y, simulation.dye[cell],
+        )?;
+    }
+    file.flush()
+}
@@ -0,0 +1,678 @@
+use std::{
+    borrow::Cow,
+    fs::File,
+    io::{BufReader, BufWriter},
+    iter,
+    path::PathBuf,
+    process::exit,
+};
+
+use clap::Parser;
+use glam::{vec2, vec4, Vec2, Vec3, Vec4};
+use mac_grid_fluid::{
+    float::{from_vec2, to_f32, to_vec2},
+    heatmap::{Colormap, Heatmap, Range, Scalar},
+    input::{Controls, Input, InputRecorder, Replay, RunMode, VelocityMode},
+    lic::Lic,
+    lines::{streamline, Pathlines, Streaklines},
+    particles::{ParticleColor, Particles},
+    recorder::Recorder,
+    Cell, CellGrid, Float, Scene, Simulation, SnapshotError, Vector,
+};
+use mesh::Glyph;
+use posh::{gl, Gl};
+use render::{Batch, Graphics, Instance, BACKGROUND};
+use sdl2::{keyboard::Keycode, mouse::MouseButton};
+use tracing::{debug, error, info, subscriber::set_global_default, warn};
+use tracing_subscriber::FmtSubscriber;
+
+mod mesh;
+mod render;
+mod shader;
+
+const WIDTH: u32 = 1280;
+const HEIGHT: u32 = 720;
+
+/// Interactive viewer, drag with the left mouse button to push the fluid.
+#[derive(Parser)]
+struct Args {
+    /// Scene file to load, the built-in default scene otherwise.
+    scene: Option<PathBuf>,
+
+    /// Where F5 saves the simulation state and F9 loads it from.
+    #[arg(long, default_value = "snapshot.bin")]
+    snapshot: PathBuf,
+
+    /// Record energy, enstrophy and divergence of every step to this file, as
+    /// JSON lines for `.jsonl` files and CSV otherwise.
+    #[arg(long)]
+    record: Option<PathBuf>,
+
+    /// Record mouse and key inputs with their frame numbers to this file.
+    #[arg(long)]
+    record_input: Option<PathBuf>,
+
+    /// Feed the inputs recorded in this file back frame by frame, live input
+    /// takes over once they run out.
+    #[arg(long)]
+    replay: Option<PathBuf>,
+
+    /// Glyph of the velocity arrows.
+    #[arg(long, value_enum, default_value_t = Glyph::Arrow)]
+    field_glyph: Glyph,
+
+    /// Glyph of the particles.
+    #[arg(long, value_enum, default_value_t = Glyph::Arrow)]
+    particle_glyph: Glyph,
+
+    /// What particles are coloured by, `age` or `speed`.
+    #[arg(long, default_value = "speed")]
+    particle_color: ParticleColor,
+
+    /// Colour map of the particles.
+    #[arg(long, default_value = "viridis")]
+    particle_colormap: Colormap,
+
+    /// Mesh of the `custom` glyph, a Wavefront `.obj` file or a CSV file with
+    /// one `x,y,z` vertex per line, three per triangle.
+    #[arg(long)]
+    mesh: Option<PathBuf>,
+
+    /// Field to colour the cells by from the start, keys 1 to 5 pick one of
+    /// pressure, divergence, vorticity, speed and dye later and 0 hides it.
+    #[arg(long)]
+    heatmap: Option<Scalar>,
+
+    /// Colour map of the heatmap: viridis, coolwarm or grayscale.
+    #[arg(long, default_value = "viridis")]
+    colormap: Colormap,
+
+    /// Values at the ends of the colour map, `min,max` or `auto` for the
+    /// range of every frame.
+    #[arg(long, default_value = "auto", allow_hyphen_values = true)]
+    range: Range,
+
+    /// Lines start every this many cells, zero leaves only the seeds added
+    /// with the middle mouse button.
+    #[arg(long, default_value_t = 5)]
+    seed_spacing: u32,
+
+    /// Most points per line.
+    #[arg(long, default_value_t = 200)]
+    line_points: usize,
+
+    /// Texels per cell of the line integral convolution.
+    #[arg(long, default_value_t = 2)]
+    lic_resolution: u32,
+
+    /// Cells the line integral convolution averages over on either side.
+    #[arg(long, default_value_t = 8.)]
+    lic_length: Float,
+
+    /// Frames between updates of the line integral convolution.
+    #[arg(long, default_value_t = 4)]
+    lic_interval: u64,
+
+    /// Colour the line integral convolution by speed.
+    #[arg(long)]
+    lic_tint: Option<Colormap>,
+}
+
+/// Segments of the legend bar.
+const LEGEND_SEGMENTS: usize = 64;
+
+fn main() {
+    set_global_default(FmtSubscriber::default()).unwrap();
+
+    let args = Args::parse();
+    let scene = match args.scene.as_ref().map(Scene::load).transpose() {
+        Ok(scene) => scene.unwrap_or_default(),
+        Err(err) => {
+            error!("{err}");
+            exit(1);
+        }
+    };
+    let mut simulation = match scene.simulation() {
+        Ok(simulation) => simulation,
+        Err(err) => {
+            error!("{err}");
+            exit(1);
+        }
+    };
+    let custom = match &args.mesh {
+        Some(path) => match mesh::load(path) {
+            Ok(custom) => custom,
+            Err(err) => {
+                error!("{err}");
+                exit(1);
+            }
+        },
+        None if [args.field_glyph, args.particle_glyph].contains(&Glyph::Custom) => {
+            error!("the custom glyph needs a --mesh");
+            exit(1);
+        }
+        None => Vec::new(),
+    };
+
+    let sdl = sdl2::init().unwrap();
+    let video = sdl.video().unwrap();
+
+    let gl_attr = video.gl_attr();
+    gl_attr.set_context_profile(sdl2::video::GLProfile::GLES);
+    gl_attr.set_context_version(3, 0);
+
+    let mut window = video
+        .window("MAC Grid Fluid", WIDTH, HEIGHT)
+        .opengl()
+        .build()
+        .unwrap();
+
+    let _gl_context = window.gl_create_context().unwrap();
+    let gl = unsafe {
+        glow::Context::from_loader_function(|s| video.gl_get_proc_address(s) as *const _)
+    };
+    let gl = gl::Context::new(gl).unwrap();
+    // The legend, the cursor, either the velocities or the particles and
+    // their trails, and the heatmap beneath them.
+    let graphics = Graphics::new(gl, 5, &custom).unwrap();
+
+    let mut event_loop = sdl.event_pump().unwrap();
+
+    // Replays only match the recording if both sum in the same order.
+    simulation.deterministic |= args.record_input.is_some() || args.replay.is_some();
+    let mut steps = 0;
+    let mut recorder = match args.record.as_ref().map(Recorder::create).transpose() {
+        Ok(recorder) => recorder,
+        Err(err) => {
+            error!("could not create record file: {err}");
+            exit(1);
+        }
+    };
+
+    let mut input_recorder = match &args.record_input {
+        Some(path) => match InputRecorder::create(path, &simulation) {
+            Ok(input_recorder) => Some(input_recorder),
+            Err(err) => {
+                error!("could not create input recording: {err}");
+                exit(1);
+            }
+        },
+        None => None,
+    };
+    let mut replay = match args.replay.as_ref().map(Replay::load).transpose() {
+        Ok(replay) => replay,
+        Err(err) => {
+            error!("{err}");
+            exit(1);
+        }
+    };
+    if let Some(replay) = &replay {
+        if replay.initial_hash != simulation.state_hash() {
+            warn!("the replay was recorded from a different initial state");
+        }
+    }
+
+    let mut controls = Controls::new(scene.viewer.interaction_radius);
+    controls.heatmap = args.heatmap;
+    let mut title = String::new();
+
+    let mut particles = Particles::new(scene.particles.clone());
+
+    let mut seeds = grid_seeds(&simulation, args.seed_spacing);
+    let mut pathlines = Pathlines::new(&seeds, args.line_points);
+    let mut streaklines = Streaklines::new(&seeds, args.line_points);
+
+    let mut lic = Lic::new(
+        simulation.dimensions,
+        args.lic_resolution.max(1),
+        args.lic_length,
+        0,
+    );
+    let mut lic_texels = Vec::new();
+    let mut lic_frame = None;
+
+    for frame in 0.. {
+        let mut cell_size = to_f32(simulation.cell_size);
+        let mut cell_offset = Vec2::splat(2. * cell_size);
+
+        let mut inputs = Vec::new();
+        for event in event_loop.poll_iter() {
+            type E = sdl2::event::Event;
+
+            let input = match event {
+                E::MouseMotion {
+                    mousestate,
+                    x,
+                    y,
+                    xrel,
+                    yrel,
+                    ..
+                } => {
+                    let position = vec2(x as f32, (HEIGHT as i32 - y) as f32) - cell_offset;
+                    let velocity = if mousestate.left() {
+                        vec2(xrel as f32, -yrel as f32)
+                    } else {
+                        Vec2::ZERO
+                    };
+                    inputs.push(Input::Cursor {
+                        position: from_vec2(position),
+                        velocity: from_vec2(velocity),
+                    });
+
+                    if !mousestate.right() {
+                        continue;
+                    }
+                    Input::Particle(from_vec2(position))
+                }
+                E::KeyDown {
+                    keycode: Some(keycode),
+                    repeat: false,
+                    ..
+                } => match keycode {
+                    Keycode::R => Input::Run(RunMode::Play),
+                    Keycode::P => Input::Run(RunMode::Step),
+                    Keycode::H => Input::Show(VelocityMode::Hide),
+                    Keycode::C => Input::Show(VelocityMode::Combined),
+                    Keycode::S => Input::Show(VelocityMode::Staggered),
+                    Keycode::L => Input::Show(VelocityMode::Streamlines),
+                    Keycode::T => Input::Show(VelocityMode::Pathlines),
+                    Keycode::K => Input::Show(VelocityMode::Streaklines),
+                    Keycode::I => Input::Show(VelocityMode::Lic),
+                    Keycode::Num0 => Input::Heatmap(None),
+                    Keycode::Num1 => Input::Heatmap(Some(Scalar::Pressure)),
+                    Keycode::Num2 => Input::Heatmap(Some(Scalar::Divergence)),
+                    Keycode::Num3 => Input::Heatmap(Some(Scalar::Vorticity)),
+                    Keycode::Num4 => Input::Heatmap(Some(Scalar::Speed)),
+                    Keycode::Num5 => Input::Heatmap(Some(Scalar::Dye)),
+                    Keycode::Space => Input::Step,
+                    Keycode::F5 => Input::Save,
+                    Keycode::F9 => Input::Load,
+                    _ => continue,
+                },
+                E::MouseButtonDown {
+                    mouse_btn: MouseButton::Middle,
+                    x,
+                    y,
+                    ..
+                } => Input::Seed(from_vec2(
+                    vec2(x as f32, (HEIGHT as i32 - y) as f32) - cell_offset,
+                )),
+                E::Quit { .. } => Input::Quit,
+                _ => continue,
+            };
+            inputs.push(input);
+        }
+        if let Some(playing) = &mut replay {
+            // Live input other than closing the window waits for the end.
+            inputs.retain(|input| *input == Input::Quit);
+            // Keep the window open after a recorded quit.
+            let recorded = playing.frame(frame).into_iter();
+            inputs.splice(0..0, recorded.filter(|input| *input != Input::Quit));
+            if playing.is_finished() {
+                info!(frame, "replay finished");
+                replay = None;
+            }
+        }
+
+        let mut step = false;
+        let mut restart_lines = false;
+        for input in inputs {
+            if let Some(input_recorder) = &mut input_recorder {
+                if let Err(err) = input_recorder.record(frame, &input) {
+                    error!("stopped recording inputs: {err}");
+                    input_recorder = None;
+                }
+            }
+            controls.apply(&input);
+
+            match input {
+                Input::Particle(position) => {
+                    particles.add(position);
+                    debug!(num_particles = particles.particles.len());
+                }
+                Input::Seed(position) => {
+                    seeds.push(position);
+                    restart_lines = true;
+                }
+                Input::Show(_) => restart_lines = true,
+                Input::Step => {
+                    info!("step");
+                    step = true;
+                }
+                Input::Save => {
+                    let saved = File::create(&args.snapshot)
+                        .and_then(|file| simulation.save(BufWriter::new(file)));
+                    match saved {
+                        Ok(()) => info!(path = %args.snapshot.display(), "saved snapshot"),
+                        Err(err) => error!("could not save snapshot: {err}"),
+                    }
+                }
+                Input::Load => {
+                    let loaded = File::open(&args.snapshot)
+                        .map_err(SnapshotError::from)
+                        .and_then(|file| Simulation::load(BufReader::new(file)));
+                    match loaded {
+                        Ok(loaded) => {
+                            let deterministic = simulation.deterministic;
+                            simulation = loaded;
+                            simulation.deterministic |= deterministic;
+                            // The snapshot may come from another grid.
+                            cell_size = to_f32(simulation.cell_size);
+                            cell_offset = Vec2::splat(2. * cell_size);
+                            seeds = grid_seeds(&simulation, args.seed_spacing);
+                            restart_lines = true;
+                            particles.reset();
+                            info!(path = %args.snapshot.display(), "loaded snapshot");
+                        }
+                        Err(err) => error!("{err}"),
+                    }
+                }
+                Input::Quit => {
+                    if let Some(Err(err)) = input_recorder.as_mut().map(InputRecorder::flush) {
+                        error!("could not save input recording: {err}");
+                    }
+                    return;
+                }
+                Input::Cursor { .. } | Input::Run(_) | Input::Heatmap(_) => {}
+            }
+        }
+
+        if restart_lines {
+            pathlines = Pathlines::new(&seeds, args.line_points);
+            streaklines = Streaklines::new(&seeds, args.line_points);
+            lic_frame = None;
+        }
+
+        if controls.advance(&mut simulation, step) {
+            steps += 1;
+
+            particles.advance(&simulation);
+            match controls.velocity_mode {
+                VelocityMode::Pathlines => pathlines.advance(&simulation),
+                VelocityMode::Streaklines => streaklines.advance(&simulation),
+                _ => {}
+            }
+
+            let recorded = recorder
+                .as_mut()
+                .map_or(Ok(()), |recorder| recorder.record(&simulation, steps));
+            if let Err(err) = recorded {
+                error!("stopped recording: {err}");
+                recorder = None;
+            }
+        }
+        if step {
+            info!(diagnostics = ?simulation.diagnostics());
+        }
+
+        let cell_to_instance = |cell: Cell| Instance::<Gl> {
+            model_to_view: glam::Mat4::from_cols(
+                cell.velocity.extend(0.).extend(0.) * cell_size,
+                vec4(-cell.velocity.y, cell.velocity.x, 0., 0.).normalize_or_zero() * cell_size,
+                Vec4::Z,
+                (cell_offset + cell.position).extend(0.).extend(1.),
+            )
+            .into(),
+            color: cell.color.into(),
+        };
+
+        let cursor_cell = Cell {
+            position: to_vec2(controls.cursor),
+            velocity: to_vec2(controls.velocity),
+            color: Vec3::Z,
+        };
+        let cursor = Batch {
+            glyph: Glyph::Arrow,
+            instances: vec![cell_to_instance(cursor_cell)].into(),
+        };
+        let particle_colors = match controls.velocity_mode {
+            VelocityMode::Hide => {
+                particles.colors(&simulation, args.particle_color, args.particle_colormap)
+            }
+            _ => Vec::new(),
+        };
+        let trails = trail_batch(&particles, &particle_colors, cell_size, &cell_to_instance);
+        let field = match controls.velocity_mode {
+            VelocityMode::Hide => Batch {
+                glyph: args.particle_glyph,
+                instances: particles
+                    .particles
+                    .iter()
+                    .zip(&particle_colors)
+                    .map(|(particle, color)| Cell {
+                        position: to_vec2(particle.position),
+                        // Markers keep their size while particles stand still.
+                        velocity: match args.particle_glyph {
+                            Glyph::Circle => Vec2::X,
+                            _ => to_vec2(simulation.interpolate_velocity(particle.position))
+                                .normalize_or_zero(),
+                        },
+                        color: *color,
+                    })
+                    .map(cell_to_instance)
+                    .collect(),
+            },
+            VelocityMode::Combined => Batch {
+                glyph: args.field_glyph,
+                instances: simulation.cells().map(cell_to_instance).collect(),
+            },
+            VelocityMode::Staggered => Batch {
+                glyph: args.field_glyph,
+                instances: simulation
+                    .velocities_x()
+                    .chain(simulation.velocities_y())
+                    .map(cell_to_instance)
+                    .collect(),
+            },
+            VelocityMode::Streamlines => {
+                let lines = seeds
+                    .iter()
+                    .map(|&seed| streamline(&simulation, seed, 0.5, args.line_points));
+                line_batch(lines, Vec3::new(1., 0.5, 0.), cell_size, &cell_to_instance)
+            }
+            VelocityMode::Pathlines => line_batch(
+                pathlines.lines.iter().cloned(),
+                Vec3::new(0., 0.6, 1.),
+                cell_size,
+                &cell_to_instance,
+            ),
+            VelocityMode::Streaklines => line_batch(
+                streaklines
+                    .lines
+                    .iter()
+                    .map(|line| line.iter().copied().collect()),
+                Vec3::new(0.8, 0., 0.8),
+                cell_size,
+                &cell_to_instance,
+            ),
+            VelocityMode::Lic => {
+                let stale = lic_frame.is_none_or(|computed| frame >= computed + args.lic_interval);
+                if stale {
+                    if lic.dimensions() != simulation.dimensions * lic.resolution {
+                        lic = Lic::new(simulation.dimensions, lic.resolution, lic.length, 0);
+                    }
+                    let colors = lic.colors(&simulation, args.lic_tint);
+                    lic_texels = texels(&lic, &colors, &simulation, cell_offset);
+                    lic_frame = Some(frame);
+                }
+                Batch {
+                    glyph: Glyph::Square,
+                    instances: Cow::Borrowed(&lic_texels),
+                }
+            }
+        };
+
+        let heatmap = controls.heatmap.map(|scalar| Heatmap {
+            scalar,
+            colormap: args.colormap,
+            range: args.range,
+        });
+        let (cells, legend, limits) = match heatmap {
+            Some(heatmap) => {
+                let (colors, limits) = heatmap.colors(&simulation);
+                let cells = colors
+                    .enumerate()
+                    .map(|(cell, color)| Cell {
+                        position: to_vec2(colors.position(cell) * simulation.cell_size),
+                        velocity: Vec2::X,
+                        color: *color,
+                    })
+                    .map(cell_to_instance)
+                    .collect();
+                (cells, legend_bar(heatmap.colormap), Some(limits))
+            }
+            None => (Vec::new(), Vec::new(), None),
+        };
+        let cells = Batch {
+            glyph: Glyph::Square,
+            instances: cells.into(),
+        };
+        let legend = Batch {
+            glyph: Glyph::Square,
+            instances: legend.into(),
+        };
+
+        // The window title doubles as the legend's labels.
+        let new_title = match (controls.heatmap, limits) {
+            (Some(scalar), Some([min, max])) => {
+                format!(
+                    "MAC Grid Fluid - {} from {min:.3} to {max:.3}",
+                    scalar.name()
+                )
+            }
+            _ => "MAC Grid Fluid".to_string(),
+        };
+        if new_title != title {
+            title = new_title;
+            if let Err(err) = window.set_title(&title) {
+                error!("could not set the window title: {err}");
+            }
+        }
+
+        graphics
+            .draw(&[legend, cursor, field, trails, cells])
+            .unwrap();
+        window.gl_swap_window();
+    }
+}
+
+/// Seeds at the centres of every `spacing`-th cell, none for zero.
+fn grid_seeds(simulation: &Simulation, spacing: u32) -> Vec<Vector> {
+    if spacing == 0 {
+        return Vec::new();
+    }
+    simulation
+        .pressures
+        .indices()
+        .filter(|cell| cell.x % spacing == spacing / 2 && cell.y % spacing == spacing / 2)
+        .map(|cell| simulation.pressures.position(cell) * simulation.cell_size)
+        .collect()
+}
+
+/// One segment per pair of consecutive points of every line, in cells as
+/// `cell_to_instance` scales velocities by the cell size.
+fn line_batch(
+    lines: impl Iterator<Item = Vec<Vector>>,
+    color: Vec3,
+    cell_size: f32,
+    cell_to_instance: &impl Fn(Cell) -> Instance<Gl>,
+) -> Batch<'static> {
+    Batch {
+        glyph: Glyph::Line,
+        instances: lines
+            .flat_map(|line| {
+                line.windows(2)
+                    .map(|pair| (to_vec2(pair[0]), to_vec2(pair[1])))
+                    .collect::<Vec<_>>()
+            })
+            .map(|(start, end)| Cell {
+                position: start,
+                velocity: (end - start) / cell_size,
+                color,
+            })
+            .map(cell_to_instance)
+            .collect(),
+    }
+}
+
+/// The trails of the particles with `colors`, one segment per pair of
+/// positions, fading into the background towards their ends. Empty without
+/// colours.
+fn trail_batch(
+    particles: &Particles,
+    colors: &[Vec3],
+    cell_size: f32,
+    cell_to_instance: &impl Fn(Cell) -> Instance<Gl>,
+) -> Batch<'static> {
+    let trail = particles.settings.trail as f32;
+    Batch {
+        glyph: Glyph::Line,
+        instances: particles
+            .particles
+            .iter()
+            .zip(colors)
+            .flat_map(|(particle, &color)| {
+                let points = iter::once(particle.position)
+                    .chain(particle.trail.iter().copied())
+                    .map(to_vec2)
+                    .collect::<Vec<_>>();
+                points
+                    .windows(2)
+                    .enumerate()
+                    .map(|(i, pair)| Cell {
+                        position: pair[1],
+                        velocity: (pair[0] - pair[1]) / cell_size,
+                        color: color.lerp(BACKGROUND, (i + 1) as f32 / (trail + 1.)),
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .map(cell_to_instance)
+            .collect(),
+    }
+}
+
+/// A square per texel of the line integral convolution.
+fn texels(
+    lic: &Lic,
+    colors: &CellGrid<Vec3>,
+    simulation: &Simulation,
+    cell_offset: Vec2,
+) -> Vec<Instance<Gl>> {
+    let size = to_f32(simulation.cell_size) / lic.resolution as f32;
+    colors
+        .enumerate()
+        .map(|(texel, color)| Instance {
+            model_to_view: glam::Mat4::from_cols(
+                Vec4::X * size,
+                Vec4::Y * size,
+                Vec4::Z,
+                (cell_offset + to_vec2(lic.position(simulation, texel)))
+                    .extend(0.)
+                    .extend(1.),
+            )
+            .into(),
+            color: (*color).into(),
+        })
+        .collect()
+}
+
+/// The colour map from bottom to top along the right edge of the window.
+fn legend_bar(colormap: Colormap) -> Vec<Instance<Gl>> {
+    let size = vec2(16., (HEIGHT as f32 - 80.) / LEGEND_SEGMENTS as f32);
+    (0..LEGEND_SEGMENTS)
+        .map(|i| {
+            let centre = vec2(WIDTH as f32 - 20., 40. + (i as f32 + 0.5) * size.y);
+            let t = (i as f32 + 0.5) / LEGEND_SEGMENTS as f32;
+            Instance {
+                model_to_view: glam::Mat4::from_cols(
+                    Vec4::X * size.x,
+                    Vec4::Y * size.y,
+                    Vec4::Z,
+                    centre.extend(0.).extend(1.),
+                )
+                .into(),
+                color: colormap.color(t.into()).into(),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,306 @@
+//! Meshes of the glyphs drawn for every instance, as triangle lists in the
+//! frame of the instance: `x` runs along its velocity, scaled by the speed,
+//! and `y` across it, in cells.
+
+use std::{
+    error,
+    f32::consts::{FRAC_PI_2, PI, TAU},
+    fmt, fs, io,
+    path::Path,
+};
+
+use clap::ValueEnum;
+use glam::{vec2, vec3, Vec2, Vec3};
+
+const ARROW: &str = include_str!("arrow.csv");
+
+/// Segments of the rounded glyphs.
+const SEGMENTS: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Glyph {
+    Arrow,
+    /// A thin segment, as in hedgehog plots.
+    Line,
+    Triangle,
+    /// A pointed head with a rounded back.
+    Cone,
+    /// A marker that ignores the direction, for particles.
+    Circle,
+    /// A square of one cell around the instance, for the heatmap.
+    Square,
+    /// The mesh given with `--mesh`.
+    Custom,
+}
+
+impl Glyph {
+    pub const ALL: [Glyph; 7] = [
+        Glyph::Arrow,
+        Glyph::Line,
+        Glyph::Triangle,
+        Glyph::Cone,
+        Glyph::Circle,
+        Glyph::Square,
+        Glyph::Custom,
+    ];
+
+    /// The built-in mesh, none for [`Glyph::Custom`].
+    pub fn mesh(self) -> Option<Vec<Vec3>> {
+        let mesh = match self {
+            Glyph::Arrow => parse_csv(ARROW).expect("embedded arrow mesh"),
+            Glyph::Line => quad(vec2(0., -0.05), vec2(1., 0.05)),
+            Glyph::Triangle => [vec2(0., -0.25), vec2(1., 0.), vec2(0., 0.25)]
+                .map(|corner| corner.extend(0.))
+                .to_vec(),
+            Glyph::Cone => {
+                let centre = vec2(0.3, 0.);
+                let back = (0..=SEGMENTS).map(|i| {
+                    let angle = FRAC_PI_2 + PI * i as f32 / SEGMENTS as f32;
+                    centre + 0.3 * Vec2::from_angle(angle)
+                });
+                fan(
+                    centre,
+                    [vec2(1., 0.)].into_iter().chain(back).chain([vec2(1., 0.)]),
+                )
+            }
+            Glyph::Circle => fan(
+                Vec2::ZERO,
+                (0..=SEGMENTS).map(|i| 0.25 * Vec2::from_angle(TAU * i as f32 / SEGMENTS as f32)),
+            ),
+            Glyph::Square => quad(Vec2::splat(-0.5), Vec2::splat(0.5)),
+            Glyph::Custom => return None,
+        };
+        Some(mesh)
+    }
+}
+
+fn quad(min: Vec2, max: Vec2) -> Vec<Vec3> {
+    [min, vec2(max.x, min.y), max, min, max, vec2(min.x, max.y)]
+        .map(|corner| corner.extend(0.))
+        .to_vec()
+}
+
+/// Triangles from `centre` to each pair of consecutive points of the outline.
+fn fan(centre: Vec2, outline: impl Iterator<Item = Vec2>) -> Vec<Vec3> {
+    let outline = outline.collect::<Vec<_>>();
+    outline
+        .windows(2)
+        .flat_map(|pair| [centre, pair[0], pair[1]])
+        .map(|corner| corner.extend(0.))
+        .collect()
+}
+
+#[derive(Debug)]
+pub enum MeshError {
+    Io(io::Error),
+    /// A line that could not be read, counting from one.
+    Line {
+        line: usize,
+        message: String,
+    },
+    /// A vertex count that is zero or not a multiple of three.
+    Triangles(usize),
+}
+
+impl fmt::Display for MeshError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MeshError::Io(err) => write!(f, "could not read mesh: {err}"),
+            MeshError::Line { line, message } => {
+                write!(f, "invalid mesh on line {line}: {message}")
+            }
+            MeshError::Triangles(count) => {
+                write!(f, "mesh has {count} vertices, which are no triangles")
+            }
+        }
+    }
+}
+
+impl error::Error for MeshError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            MeshError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for MeshError {
+    fn from(err: io::Error) -> Self {
+        MeshError::Io(err)
+    }
+}
+
+/// Reads a Wavefront `.obj` file, or a CSV file with one `x,y,z` vertex per
+/// line and three per triangle.
+pub fn load(path: impl AsRef<Path>) -> Result<Vec<Vec3>, MeshError> {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path)?;
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("obj") => parse_obj(&text),
+        _ => parse_csv(&text),
+    }
+}
+
+fn parse_csv(text: &str) -> Result<Vec<Vec3>, MeshError> {
+    let mut vertices = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let coordinates = line
+            .split(',')
+            .map(|coordinate| coordinate.trim().parse().ok())
+            .collect::<Option<Vec<f32>>>();
+        match coordinates.as_deref() {
+            Some(&[x, y, z]) => vertices.push(vec3(x, y, z)),
+            _ => {
+                return Err(MeshError::Line {
+                    line: index + 1,
+                    message: format!("expected `x,y,z`, found {line:?}"),
+                })
+            }
+        }
+    }
+    triangles(vertices)
+}
+
+/// Vertices and faces, polygons are split into fans of triangles. Normals,
+/// texture coordinates, groups and materials are skipped.
+fn parse_obj(text: &str) -> Result<Vec<Vec3>, MeshError> {
+    let mut positions = Vec::new();
+    let mut vertices = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let invalid = |message: String| MeshError::Line {
+            line: index + 1,
+            message,
+        };
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("v") => {
+                let coordinates = words
+                    .take(3)
+                    .map(|coordinate| coordinate.parse().ok())
+                    .collect::<Option<Vec<f32>>>();
+                match coordinates.as_deref() {
+                    Some(&[x, y, z]) => positions.push(vec3(x, y, z)),
+                    _ => return Err(invalid(format!("expected `v x y z`, found {line:?}"))),
+                }
+            }
+            Some("f") => {
+                let corners = words
+                    .map(|corner| {
+                        // `v`, `v/vt`, `v//vn` or `v/vt/vn`, negative from the end.
+                        let reference = corner.split('/').next().unwrap_or_default();
+                        let reference = reference
+                            .parse::<i64>()
+                            .map_err(|_| invalid(format!("invalid vertex {corner:?}")))?;
+                        let position = if reference < 0 {
+                            positions.len() as i64 + reference
+                        } else {
+                            reference - 1
+                        };
+                        usize::try_from(position)
+                            .ok()
+                            .and_then(|position| positions.get(position).copied())
+                            .ok_or_else(|| invalid(format!("no vertex {reference}")))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                if corners.len() < 3 {
+                    return Err(invalid(format!("face with {} vertices", corners.len())));
+                }
+                for pair in corners[1..].windows(2) {
+                    vertices.extend([corners[0], pair[0], pair[1]]);
+                }
+            }
+            _ => {}
+        }
+    }
+    triangles(vertices)
+}
+
+fn triangles(vertices: Vec<Vec3>) -> Result<Vec<Vec3>, MeshError> {
+    if vertices.is_empty() || vertices.len() % 3 != 0 {
+        return Err(MeshError::Triangles(vertices.len()));
+    }
+    Ok(vertices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line_of(result: Result<Vec<Vec3>, MeshError>) -> usize {
+        match result {
+            Err(MeshError::Line { line, .. }) => line,
+            other => panic!("{other:?}"),
+        }
+    }
+
+    #[test]
+    fn builtin_meshes_are_triangles() {
+        for glyph in Glyph::ALL {
+            if let Some(mesh) = glyph.mesh() {
+                assert!(triangles(mesh).is_ok(), "{glyph:?}");
+            }
+        }
+        assert!(Glyph::Custom.mesh().is_none());
+    }
+
+    #[test]
+    fn csv_vertices() {
+        let mesh = parse_csv("0,0,0\n\n 1, 0, 0\n0,1,0.5\n").unwrap();
+        assert_eq!(mesh, [Vec3::ZERO, Vec3::X, vec3(0., 1., 0.5)]);
+    }
+
+    #[test]
+    fn malformed_csv() {
+        assert_eq!(line_of(parse_csv("0,0,0\n1,0\n0,1,0\n")), 2);
+        assert_eq!(line_of(parse_csv("0,0,0\n\n1,x,0\n")), 3);
+        assert!(matches!(
+            parse_csv("0,0,0\n1,0,0\n"),
+            Err(MeshError::Triangles(2))
+        ));
+        assert!(matches!(parse_csv(""), Err(MeshError::Triangles(0))));
+    }
+
+    #[test]
+    fn obj_polygons_are_fans() {
+        let mesh = parse_obj(
+            "# square\n\
+             o square\n\
+             v 0 0 0\n\
+             v 1 0 0\n\
+             v 1 1 0\n\
+             v 0 1 0\n\
+             vt 0 0\n\
+             vn 0 0 1\n\
+             f 1/1 2/1/1 3//1 4\n",
+        )
+        .unwrap();
+        let [a, b, c, d] = [Vec3::ZERO, Vec3::X, vec3(1., 1., 0.), Vec3::Y];
+        assert_eq!(mesh, [a, b, c, a, c, d]);
+    }
+
+    #[test]
+    fn obj_negative_indices_count_from_the_end() {
+        let mesh =
+            parse_obj("v 0 0 0\nv 1 0 0\nv 0 1 0\nf -3 -2 -1\nv 0 0 1\nf -1 -4 2\n").unwrap();
+        assert_eq!(
+            mesh,
+            [Vec3::ZERO, Vec3::X, Vec3::Y, Vec3::Z, Vec3::ZERO, Vec3::X]
+        );
+    }
+
+    #[test]
+    fn malformed_obj() {
+        let vertices = "v 0 0 0\nv 1 0 0\nv 0 1 0\n";
+        assert_eq!(line_of(parse_obj("v 0 0\n")), 1);
+        assert_eq!(line_of(parse_obj(&format!("{vertices}f 1 2 x\n"))), 4);
+        assert_eq!(line_of(parse_obj(&format!("{vertices}\nf 1 2 4\n"))), 5);
+        assert_eq!(line_of(parse_obj(&format!("{vertices}f 0 1 2\n"))), 4);
+        assert_eq!(line_of(parse_obj(&format!("{vertices}f -4 1 2\n"))), 4);
+        assert_eq!(line_of(parse_obj(&format!("{vertices}f 1 2\n"))), 4);
+        assert!(matches!(parse_obj(vertices), Err(MeshError::Triangles(0))));
+    }
+}
@@ -0,0 +1,125 @@
+use std::borrow::Cow;
+
+use posh::{gl, sl, Block, BlockDom, Gl, Sl, VsInterface, VsInterfaceDom};
+
+use crate::{
+    mesh::Glyph,
+    shader::{fragment_shader, vertex_shader},
+    HEIGHT, WIDTH,
+};
+
+/// Colour the screen is cleared to.
+pub const BACKGROUND: glam::Vec3 = glam::Vec3::new(0.1, 0.2, 0.3);
+
+#[derive(Clone, Copy, Block)]
+#[repr(C)]
+pub struct Camera<D: BlockDom> {
+    pub world_to_view: D::Mat4,
+    pub view_to_screen: D::Mat4,
+}
+
+#[derive(Clone, Copy, Block)]
+#[repr(C)]
+pub struct Instance<D: BlockDom> {
+    pub model_to_view: D::Mat4,
+    pub color: D::Vec3,
+}
+
+#[derive(Copy, Clone, VsInterface)]
+pub struct VsInput<D: VsInterfaceDom> {
+    pub instance: D::Block<Instance<Sl>>,
+    pub model_pos: D::Block<sl::Vec3>,
+}
+
+/// Instances drawn with the same glyph, borrowed when they outlive the frame.
+pub struct Batch<'a> {
+    pub glyph: Glyph,
+    pub instances: Cow<'a, [Instance<Gl>]>,
+}
+
+pub struct Graphics {
+    program: gl::Program<Camera<Sl>, VsInput<Sl>>,
+
+    camera: gl::UniformBuffer<Camera<Gl>>,
+
+    /// One buffer per batch, in the order they are drawn.
+    instances: Vec<gl::VertexBuffer<Instance<Gl>>>,
+    /// Meshes indexed by [`Glyph`], the custom one may be empty.
+    glyphs: Vec<gl::VertexBuffer<gl::Vec3>>,
+}
+
+impl Graphics {
+    /// Room for `batches` batches per frame, `custom` is the mesh of
+    /// [`Glyph::Custom`].
+    pub fn new(
+        gl: gl::Context,
+        batches: usize,
+        custom: &[glam::Vec3],
+    ) -> Result<Self, gl::CreateError> {
+        use gl::BufferUsage::*;
+
+        let mesh = |glyph: Glyph| {
+            let mesh = glyph.mesh().unwrap_or_else(|| custom.to_vec());
+            gl.create_vertex_buffer(
+                &mesh.into_iter().map(Into::into).collect::<Vec<gl::Vec3>>(),
+                StaticDraw,
+            )
+        };
+
+        Ok(Self {
+            program: gl.create_program(vertex_shader, fragment_shader)?,
+            camera: gl.create_uniform_buffer(Camera::default(), StaticDraw)?,
+            instances: (0..batches)
+                .map(|_| gl.create_vertex_buffer(&[], StreamDraw))
+                .collect::<Result<_, _>>()?,
+            glyphs: Glyph::ALL.map(mesh).into_iter().collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Clears the screen and draws the batches, earlier ones in front.
+    pub fn draw(&self, batches: &[Batch<'_>]) -> Result<(), gl::DrawError> {
+        let mut clear = true;
+        for (buffer, batch) in self.instances.iter().zip(batches) {
+            if batch.instances.is_empty() {
+                continue;
+            }
+            buffer.set(&batch.instances);
+
+            let mut settings = gl::DrawSettings::new().with_depth_test(gl::Comparison::Less);
+            if clear {
+                settings = settings
+                    .with_clear_color(BACKGROUND.extend(1.0).to_array())
+                    .with_clear_depth(1.0);
+                clear = false;
+            }
+            self.program
+                .with_uniforms(self.camera.as_binding())
+                .with_settings(settings)
+                .draw(
+                    gl::VertexSpec::new(gl::PrimitiveMode::Triangles).with_vertex_data(VsInput {
+                        instance: buffer.as_binding().with_instancing(),
+                        model_pos: self.glyphs[batch.glyph as usize].as_binding(),
+                    }),
+                )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for Camera<Gl> {
+    fn default() -> Self {
+        Self {
+            world_to_view: glam::Mat4::IDENTITY.into(),
+            view_to_screen: {
+                let left: f32 = 0.;
+                let right: f32 = WIDTH as f32;
+                let bottom: f32 = 0.;
+                let top: f32 = HEIGHT as f32;
+                let near: f32 = -1.;
+                let far: f32 = 1.;
+                glam::Mat4::orthographic_rh_gl(left, right, bottom, top, near, far).into()
+            },
+        }
+    }
+}
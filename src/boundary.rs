@@ -0,0 +1,92 @@
+use glam::{BVec2, IVec2, UVec2};
+use serde::{Deserialize, Serialize};
+
+use crate::float::Float;
+
+/// What lies beyond one side of the domain.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Boundary {
+    /// Solid wall, nothing flows through it.
+    #[default]
+    Wall,
+    /// Fluid at zero pressure, which may flow in or out freely.
+    Open,
+    /// The opposite side, which has to be periodic as well. A simulation
+    /// treats a periodic side without its pair as a wall.
+    Periodic,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Boundaries {
+    pub left: Boundary,
+    pub right: Boundary,
+    pub bottom: Boundary,
+    pub top: Boundary,
+}
+
+impl Boundaries {
+    pub fn periodic() -> Self {
+        Self {
+            left: Boundary::Periodic,
+            right: Boundary::Periodic,
+            bottom: Boundary::Periodic,
+            top: Boundary::Periodic,
+        }
+    }
+
+    /// Axes along which both sides are periodic.
+    pub fn wrap(&self) -> BVec2 {
+        BVec2::new(
+            self.left == Boundary::Periodic && self.right == Boundary::Periodic,
+            self.bottom == Boundary::Periodic && self.top == Boundary::Periodic,
+        )
+    }
+
+    /// The side a cell outside of a domain of `dimensions` cells lies beyond.
+    pub fn beyond(&self, outside: IVec2, dimensions: UVec2) -> Boundary {
+        beyond(
+            outside,
+            dimensions,
+            [self.left, self.right, self.bottom, self.top],
+        )
+    }
+}
+
+/// Velocity of the walls along themselves, along x for the bottom and top and
+/// along y for the left and right, e.g. the lid of a cavity. Only affects the
+/// flow through viscosity.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WallVelocities {
+    pub left: Float,
+    pub right: Float,
+    pub bottom: Float,
+    pub top: Float,
+}
+
+impl WallVelocities {
+    /// The velocity of the side a cell outside of a domain of `dimensions`
+    /// cells lies beyond.
+    pub fn beyond(&self, outside: IVec2, dimensions: UVec2) -> Float {
+        beyond(
+            outside,
+            dimensions,
+            [self.left, self.right, self.bottom, self.top],
+        )
+    }
+}
+
+fn beyond<T: Copy>(outside: IVec2, dimensions: UVec2, [left, right, bottom, top]: [T; 4]) -> T {
+    if outside.x < 0 {
+        left
+    } else if outside.x >= dimensions.x as i32 {
+        right
+    } else if outside.y < 0 {
+        bottom
+    } else {
+        assert!(outside.y >= dimensions.y as i32);
+        top
+    }
+}
@@ -0,0 +1,70 @@
+use glam::uvec2;
+
+use crate::{float::Float, reduce::reduce, simulation::Simulation};
+
+/// Measures of how well a step went, see [`Simulation::diagnostics`].
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Diagnostics {
+    /// Root mean square of the divergence over the fluid cells, built from
+    /// the same outflow that the pressure solve drives to zero.
+    pub divergence_rms: Float,
+    /// Largest absolute divergence of any fluid cell.
+    pub divergence_max: Float,
+    /// See [`Simulation::kinetic_energy`].
+    pub kinetic_energy: Float,
+    /// Largest absolute face velocity.
+    pub max_speed: Float,
+    /// Cells crossed at `max_speed` in one time step, advection gets
+    /// inaccurate well above 1.
+    pub cfl: Float,
+    /// Root mean square of the pressure equation residual after the solve.
+    pub pressure_residual: Float,
+    pub pressure_iterations: u32,
+}
+
+impl Diagnostics {
+    pub(crate) fn new(simulation: &Simulation, pressure_residual: Float) -> Self {
+        let (divergence_rms, divergence_max) = divergence_norms(simulation);
+        let max_speed = simulation
+            .velocities_x
+            .iter()
+            .chain(simulation.velocities_y.iter())
+            .fold(0., |max: Float, velocity| max.max(velocity.abs()));
+
+        Self {
+            divergence_rms,
+            divergence_max,
+            kinetic_energy: simulation.kinetic_energy(),
+            max_speed,
+            cfl: max_speed * simulation.time_step / simulation.cell_size,
+            pressure_residual,
+            pressure_iterations: simulation.solver.iterations,
+        }
+    }
+}
+
+/// Root mean square and maximum of the absolute divergence over the fluid
+/// cells.
+pub(crate) fn divergence_norms(simulation: &Simulation) -> (Float, Float) {
+    let width = simulation.dimensions.x;
+    let (sum, max, count) = reduce(
+        simulation.pressures.len(),
+        simulation.deterministic,
+        (0., 0., 0),
+        |index| {
+            let cell = uvec2(index as u32 % width, index as u32 / width);
+            if simulation.solids[cell] {
+                return (0., 0., 0);
+            }
+            let divergence = simulation.divergence_at(cell);
+            (divergence * divergence, divergence.abs(), 1)
+        },
+        |(sum, max, count): (Float, Float, u32), (other_sum, other_max, other_count)| {
+            (sum + other_sum, max.max(other_max), count + other_count)
+        },
+    );
+    if count == 0 {
+        return (0., 0.);
+    }
+    ((sum / count as Float).sqrt(), max)
+}
@@ -0,0 +1,87 @@
+use std::{error, fmt, io};
+
+use glam::{IVec2, UVec2};
+
+use crate::{float::Float, input::InputError, scene::SceneError, snapshot::SnapshotError};
+
+/// Any error of this crate, for applications that handle them in one place.
+#[derive(Debug)]
+pub enum Error {
+    /// Grids need at least one cell along each axis.
+    EmptyDimensions(UVec2),
+    /// A cell size or time step that is zero, negative or not finite.
+    NotPositive {
+        name: &'static str,
+        value: Float,
+    },
+    OutOfBounds {
+        index: IVec2,
+        dimensions: UVec2,
+    },
+    Scene(SceneError),
+    Snapshot(SnapshotError),
+    Input(InputError),
+    Io(io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::EmptyDimensions(dimensions) => {
+                write!(f, "dimensions {dimensions} have no cells")
+            }
+            Error::NotPositive { name, value } => write!(f, "{name} {value} is not positive"),
+            Error::OutOfBounds { index, dimensions } => {
+                write!(f, "index {index} is outside of dimensions {dimensions}")
+            }
+            Error::Scene(err) => err.fmt(f),
+            Error::Snapshot(err) => err.fmt(f),
+            Error::Input(err) => err.fmt(f),
+            Error::Io(err) => err.fmt(f),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Scene(err) => err.source(),
+            Error::Snapshot(err) => err.source(),
+            Error::Input(err) => err.source(),
+            Error::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<SceneError> for Error {
+    fn from(err: SceneError) -> Self {
+        Error::Scene(err)
+    }
+}
+
+impl From<SnapshotError> for Error {
+    fn from(err: SnapshotError) -> Self {
+        Error::Snapshot(err)
+    }
+}
+
+impl From<InputError> for Error {
+    fn from(err: InputError) -> Self {
+        Error::Input(err)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+/// Checks the dimensions of a grid.
+pub(crate) fn check_dimensions(dimensions: UVec2) -> Result<(), Error> {
+    if dimensions.min_element() == 0 {
+        return Err(Error::EmptyDimensions(dimensions));
+    }
+    Ok(())
+}
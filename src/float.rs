@@ -0,0 +1,58 @@
+//! Floating point precision of the simulation, `f32` by default and `f64`
+//! with the `f64` feature. Everything but the [`Cell`](crate::Cell)s for
+//! display uses these types.
+
+use glam::{IVec2, UVec2};
+
+#[cfg(not(feature = "f64"))]
+mod precision {
+    pub type Float = f32;
+    pub type Vector = glam::Vec2;
+
+    /// Narrows a value for display.
+    pub fn to_f32(value: Float) -> f32 {
+        value
+    }
+
+    /// Widens a position or velocity from the viewer.
+    pub fn from_vec2(vector: glam::Vec2) -> Vector {
+        vector
+    }
+
+    /// Narrows a position or velocity for display.
+    pub fn to_vec2(vector: Vector) -> glam::Vec2 {
+        vector
+    }
+}
+
+#[cfg(feature = "f64")]
+mod precision {
+    pub type Float = f64;
+    pub type Vector = glam::DVec2;
+
+    /// Narrows a value for display.
+    pub fn to_f32(value: Float) -> f32 {
+        value as f32
+    }
+
+    /// Widens a position or velocity from the viewer.
+    pub fn from_vec2(vector: glam::Vec2) -> Vector {
+        vector.as_dvec2()
+    }
+
+    /// Narrows a position or velocity for display.
+    pub fn to_vec2(vector: Vector) -> glam::Vec2 {
+        vector.as_vec2()
+    }
+}
+
+pub use precision::{from_vec2, to_f32, to_vec2, Float, Vector};
+
+/// Converts cell coordinates or dimensions.
+pub fn from_uvec2(vector: UVec2) -> Vector {
+    Vector::new(vector.x as Float, vector.y as Float)
+}
+
+pub(crate) fn from_ivec2(vector: IVec2) -> Vector {
+    Vector::new(vector.x as Float, vector.y as Float)
+}
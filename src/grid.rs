@@ -0,0 +1,597 @@
+use std::ops::{Deref, DerefMut, Index, IndexMut};
+
+use glam::{uvec2, BVec2, IVec2, UVec2};
+
+use crate::{
+    error::{check_dimensions, Error},
+    float::{from_ivec2, from_uvec2, Float, Vector},
+};
+
+/// Direction a [`FaceGrid`] is normal to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    X,
+    Y,
+}
+
+impl Axis {
+    pub fn unit(self) -> UVec2 {
+        match self {
+            Axis::X => UVec2::X,
+            Axis::Y => UVec2::Y,
+        }
+    }
+}
+
+/// Row-major 2D storage where every entry is sampled at `index + offset`,
+/// measured in cells.
+///
+/// Wrapping uses the dimensions of the underlying cell domain, so the last
+/// column (or row) of a face grid aliases the first one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grid<T> {
+    dimensions: UVec2,
+    period: UVec2,
+    offset: Vector,
+    data: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    fn from_fn(
+        dimensions: UVec2,
+        period: UVec2,
+        offset: Vector,
+        f: impl FnMut(UVec2) -> T,
+    ) -> Self {
+        assert!(dimensions.element_product() != 0);
+
+        let data = indices(dimensions).map(f).collect();
+        Self {
+            dimensions,
+            period,
+            offset,
+            data,
+        }
+    }
+
+    pub fn dimensions(&self) -> UVec2 {
+        self.dimensions
+    }
+
+    pub fn offset(&self) -> Vector {
+        self.offset
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.data
+    }
+
+    /// Position of an entry in cells, i.e. divided by the cell size.
+    pub fn position(&self, index: UVec2) -> Vector {
+        from_uvec2(index) + self.offset
+    }
+
+    pub fn contains(&self, index: IVec2) -> bool {
+        index.cmpge(IVec2::ZERO).all() && index.as_uvec2().cmplt(self.dimensions).all()
+    }
+
+    fn idx(&self, index: UVec2) -> usize {
+        assert!(index.x < self.dimensions.x);
+        assert!(index.y < self.dimensions.y);
+        (index.x + index.y * self.dimensions.x) as usize
+    }
+
+    fn clamp(&self, index: IVec2) -> UVec2 {
+        index
+            .max(IVec2::ZERO)
+            .as_uvec2()
+            .min(self.dimensions - UVec2::ONE)
+    }
+
+    fn wrap(&self, index: IVec2) -> UVec2 {
+        index.rem_euclid(self.period.as_ivec2()).as_uvec2()
+    }
+
+    fn wrap_or_clamp(&self, index: IVec2, wrap: BVec2) -> UVec2 {
+        UVec2::select(wrap, self.wrap(index), self.clamp(index))
+    }
+
+    pub fn get(&self, index: IVec2) -> Option<&T> {
+        self.contains(index)
+            .then(|| &self.data[self.idx(index.as_uvec2())])
+    }
+
+    pub fn get_mut(&mut self, index: IVec2) -> Option<&mut T> {
+        if self.contains(index) {
+            let idx = self.idx(index.as_uvec2());
+            Some(&mut self.data[idx])
+        } else {
+            None
+        }
+    }
+
+    /// Like [`Grid::get`], with an error that tells the dimensions.
+    pub fn try_get(&self, index: IVec2) -> Result<&T, Error> {
+        let dimensions = self.dimensions;
+        self.get(index)
+            .ok_or(Error::OutOfBounds { index, dimensions })
+    }
+
+    pub fn try_get_mut(&mut self, index: IVec2) -> Result<&mut T, Error> {
+        let dimensions = self.dimensions;
+        self.get_mut(index)
+            .ok_or(Error::OutOfBounds { index, dimensions })
+    }
+
+    /// Out-of-range indices are clamped to the nearest entry.
+    pub fn clamped(&self, index: IVec2) -> &T {
+        &self[self.clamp(index)]
+    }
+
+    pub fn clamped_mut(&mut self, index: IVec2) -> &mut T {
+        let clamped = self.clamp(index);
+        &mut self[clamped]
+    }
+
+    /// Out-of-range indices wrap around the cell domain.
+    pub fn wrapped(&self, index: IVec2) -> &T {
+        &self[self.wrap(index)]
+    }
+
+    pub fn wrapped_mut(&mut self, index: IVec2) -> &mut T {
+        let wrapped = self.wrap(index);
+        &mut self[wrapped]
+    }
+
+    /// Wraps along the axes set in `wrap` and clamps along the others.
+    pub fn wrapped_or_clamped(&self, index: IVec2, wrap: BVec2) -> &T {
+        &self[self.wrap_or_clamp(index, wrap)]
+    }
+
+    /// All indices in storage order, which is row-major.
+    pub fn indices(&self) -> impl Iterator<Item = UVec2> {
+        indices(self.dimensions)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        self.data.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> + '_ {
+        self.data.iter_mut()
+    }
+
+    pub fn enumerate(&self) -> impl Iterator<Item = (UVec2, &T)> + '_ {
+        self.indices().zip(self.data.iter())
+    }
+
+    pub fn fill(&mut self, value: T)
+    where
+        T: Clone,
+    {
+        self.data.fill(value);
+    }
+
+    /// Copies all entries of a grid of the same dimensions.
+    pub fn copy_from(&mut self, other: &Grid<T>)
+    where
+        T: Copy,
+    {
+        assert_eq!(self.dimensions, other.dimensions);
+        self.data.copy_from_slice(&other.data);
+    }
+}
+
+impl<T: Send> Grid<T> {
+    /// Like `from_fn`, but evaluated row by row on the rayon thread pool with
+    /// the `parallel` feature, with the same result either way.
+    fn par_from_fn(
+        dimensions: UVec2,
+        period: UVec2,
+        offset: Vector,
+        f: impl Fn(UVec2) -> T + Sync + Send,
+    ) -> Self {
+        assert!(dimensions.element_product() != 0);
+
+        #[cfg(feature = "parallel")]
+        let data = {
+            use rayon::prelude::*;
+
+            let width = dimensions.x;
+            (0..dimensions.element_product())
+                .into_par_iter()
+                .with_min_len(width as usize)
+                .map(|i| f(uvec2(i % width, i / width)))
+                .collect()
+        };
+        #[cfg(not(feature = "parallel"))]
+        let data = indices(dimensions).map(f).collect();
+
+        Self {
+            dimensions,
+            period,
+            offset,
+            data,
+        }
+    }
+
+    /// Sets every entry from its index without allocating, row by row on the
+    /// rayon thread pool with the `parallel` feature.
+    pub fn fill_with(&mut self, f: impl Fn(UVec2) -> T + Sync + Send) {
+        let width = self.dimensions.x as usize;
+        let fill_row = |(j, row): (usize, &mut [T])| {
+            for (i, value) in row.iter_mut().enumerate() {
+                *value = f(uvec2(i as u32, j as u32));
+            }
+        };
+
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+
+            self.data
+                .par_chunks_mut(width)
+                .enumerate()
+                .for_each(fill_row);
+        }
+        #[cfg(not(feature = "parallel"))]
+        self.data.chunks_mut(width).enumerate().for_each(fill_row);
+    }
+}
+
+impl Grid<Float> {
+    /// Bilinear interpolation at a position in cells, clamping at the border.
+    pub fn sample(&self, normalized: Vector) -> Float {
+        self.sample_with(normalized, BVec2::FALSE)
+    }
+
+    /// Bilinear interpolation at a position in cells, wrapping at the border.
+    pub fn sample_wrapped(&self, normalized: Vector) -> Float {
+        self.sample_with(normalized, BVec2::TRUE)
+    }
+
+    /// Bilinear interpolation at a position in cells, wrapping along the
+    /// axes set in `wrap` and clamping along the others.
+    pub fn sample_with(&self, normalized: Vector, wrap: BVec2) -> Float {
+        let lookup = |index| *self.wrapped_or_clamped(index, wrap);
+
+        let shifted = self.near(normalized - self.offset, wrap);
+        let reference = shifted.floor().as_ivec2();
+
+        // NaN positions end up here as NaN offsets and sample NaN.
+        let Vector { x: dx, y: dy } = shifted - from_ivec2(reference);
+
+        // Away from the border the four entries are read directly, which
+        // is the common case when advecting.
+        let bound = UVec2::select(wrap, self.period, self.dimensions);
+        let [bottom_left, bottom_right, top_left, top_right] = if reference.cmpge(IVec2::ZERO).all()
+            && (reference + IVec2::ONE).as_uvec2().cmplt(bound).all()
+        {
+            let idx = self.idx(reference.as_uvec2());
+            let width = self.dimensions.x as usize;
+            [
+                self.data[idx],
+                self.data[idx + 1],
+                self.data[idx + width],
+                self.data[idx + width + 1],
+            ]
+        } else {
+            [IVec2::ZERO, IVec2::X, IVec2::Y, IVec2::ONE].map(|offset| lookup(reference + offset))
+        };
+
+        (1. - dx) * (bottom_left * (1. - dy) + top_left * dy)
+            + dx * (bottom_right * (1. - dy) + top_right * dy)
+    }
+
+    /// Moves positions far outside of the grid to where they sample the same
+    /// values, so that the entries around them are indexed without overflow.
+    fn near(&self, shifted: Vector, wrap: BVec2) -> Vector {
+        let period = from_uvec2(self.period);
+        let last = from_uvec2(self.dimensions);
+        let far_wrapped = wrap & (shifted.cmplt(-period) | shifted.cmpgt(2. * period));
+        let far_clamped = !wrap & (shifted.cmplt(Vector::NEG_ONE) | shifted.cmpgt(last));
+        Vector::select(
+            far_wrapped,
+            shifted.rem_euclid(period),
+            Vector::select(far_clamped, shifted.clamp(Vector::NEG_ONE, last), shifted),
+        )
+    }
+}
+
+impl<T> Index<UVec2> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, index: UVec2) -> &T {
+        &self.data[self.idx(index)]
+    }
+}
+
+impl<T> IndexMut<UVec2> for Grid<T> {
+    fn index_mut(&mut self, index: UVec2) -> &mut T {
+        let idx = self.idx(index);
+        &mut self.data[idx]
+    }
+}
+
+fn indices(dimensions: UVec2) -> impl Iterator<Item = UVec2> {
+    (0..dimensions.y).flat_map(move |j| (0..dimensions.x).map(move |i| uvec2(i, j)))
+}
+
+/// One value per cell, sampled at the cell centre.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CellGrid<T>(Grid<T>);
+
+impl<T> CellGrid<T> {
+    /// Fills the grid with `value`.
+    ///
+    /// # Panics
+    ///
+    /// On empty `dimensions`, [`CellGrid::try_from_fn`] returns an error instead.
+    pub fn new(dimensions: UVec2, value: T) -> Self
+    where
+        T: Clone,
+    {
+        Self::from_fn(dimensions, |_| value.clone())
+    }
+
+    /// Fills the grid with `f` of each index.
+    ///
+    /// # Panics
+    ///
+    /// On empty `dimensions`, [`CellGrid::try_from_fn`] returns an error instead.
+    pub fn from_fn(dimensions: UVec2, f: impl FnMut(UVec2) -> T) -> Self {
+        Self(Grid::from_fn(dimensions, dimensions, Vector::splat(0.5), f))
+    }
+
+    /// Like [`CellGrid::from_fn`], but fails with [`Error::EmptyDimensions`].
+    pub fn try_from_fn(dimensions: UVec2, f: impl FnMut(UVec2) -> T) -> Result<Self, Error> {
+        check_dimensions(dimensions)?;
+        Ok(Self::from_fn(dimensions, f))
+    }
+}
+
+impl<T: Send> CellGrid<T> {
+    /// See [`CellGrid::from_fn`], parallel with the `parallel` feature.
+    ///
+    /// # Panics
+    ///
+    /// On empty `dimensions`.
+    pub fn par_from_fn(dimensions: UVec2, f: impl Fn(UVec2) -> T + Sync + Send) -> Self {
+        Self(Grid::par_from_fn(
+            dimensions,
+            dimensions,
+            Vector::splat(0.5),
+            f,
+        ))
+    }
+}
+
+impl<T> Deref for CellGrid<T> {
+    type Target = Grid<T>;
+
+    fn deref(&self) -> &Grid<T> {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for CellGrid<T> {
+    fn deref_mut(&mut self) -> &mut Grid<T> {
+        &mut self.0
+    }
+}
+
+/// One value per cell face normal to `axis`, sampled at the face centre.
+///
+/// For a domain of `n` cells along `axis` there are `n + 1` faces, the first
+/// and last of which lie on the domain border.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FaceGrid<T> {
+    axis: Axis,
+    grid: Grid<T>,
+}
+
+impl<T> FaceGrid<T> {
+    /// Fills the grid with `value`.
+    ///
+    /// # Panics
+    ///
+    /// On empty `cell_dimensions`, [`FaceGrid::try_from_fn`] returns an error instead.
+    pub fn new(axis: Axis, cell_dimensions: UVec2, value: T) -> Self
+    where
+        T: Clone,
+    {
+        Self::from_fn(axis, cell_dimensions, |_| value.clone())
+    }
+
+    /// Fills the grid with `f` of each index.
+    ///
+    /// # Panics
+    ///
+    /// On empty `cell_dimensions`, [`FaceGrid::try_from_fn`] returns an error instead.
+    pub fn from_fn(axis: Axis, cell_dimensions: UVec2, f: impl FnMut(UVec2) -> T) -> Self {
+        let offset = (Vector::ONE - from_uvec2(axis.unit())) * 0.5;
+        Self {
+            axis,
+            grid: Grid::from_fn(cell_dimensions + axis.unit(), cell_dimensions, offset, f),
+        }
+    }
+
+    /// Like [`FaceGrid::from_fn`], but fails with [`Error::EmptyDimensions`].
+    pub fn try_from_fn(
+        axis: Axis,
+        cell_dimensions: UVec2,
+        f: impl FnMut(UVec2) -> T,
+    ) -> Result<Self, Error> {
+        check_dimensions(cell_dimensions)?;
+        Ok(Self::from_fn(axis, cell_dimensions, f))
+    }
+
+    pub fn axis(&self) -> Axis {
+        self.axis
+    }
+
+    pub fn cell_dimensions(&self) -> UVec2 {
+        self.grid.dimensions - self.axis.unit()
+    }
+
+    /// Whether the face lies on the border of the cell domain.
+    pub fn is_boundary(&self, face: UVec2) -> bool {
+        let along = face.dot(self.axis.unit());
+        along == 0 || along == self.cell_dimensions().dot(self.axis.unit())
+    }
+}
+
+impl<T: Send> FaceGrid<T> {
+    /// See [`FaceGrid::from_fn`], parallel with the `parallel` feature.
+    ///
+    /// # Panics
+    ///
+    /// On empty `cell_dimensions`.
+    pub fn par_from_fn(
+        axis: Axis,
+        cell_dimensions: UVec2,
+        f: impl Fn(UVec2) -> T + Sync + Send,
+    ) -> Self {
+        let offset = (Vector::ONE - from_uvec2(axis.unit())) * 0.5;
+        Self {
+            axis,
+            grid: Grid::par_from_fn(cell_dimensions + axis.unit(), cell_dimensions, offset, f),
+        }
+    }
+}
+
+impl<T> Deref for FaceGrid<T> {
+    type Target = Grid<T>;
+
+    fn deref(&self) -> &Grid<T> {
+        &self.grid
+    }
+}
+
+impl<T> DerefMut for FaceGrid<T> {
+    fn deref_mut(&mut self) -> &mut Grid<T> {
+        &mut self.grid
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{ivec2, BVec2};
+
+    use super::*;
+
+    /// Entries numbered `x + 10 * y`.
+    fn numbered(index: UVec2) -> Float {
+        (index.x + 10 * index.y) as Float
+    }
+
+    #[test]
+    fn storage_is_row_major() {
+        let grid = CellGrid::from_fn(uvec2(3, 2), numbered);
+        assert_eq!(grid.as_slice(), [0., 1., 2., 10., 11., 12.]);
+        assert_eq!(grid[uvec2(2, 1)], 12.);
+        assert_eq!(grid.position(uvec2(2, 1)), Vector::new(2.5, 1.5));
+        assert_eq!(
+            grid.indices().collect::<Vec<_>>(),
+            [(0, 0), (1, 0), (2, 0), (0, 1), (1, 1), (2, 1)].map(|(x, y)| uvec2(x, y))
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn indexing_out_of_bounds_panics() {
+        let grid = CellGrid::new(uvec2(3, 2), 0.);
+        let _ = grid[uvec2(3, 0)];
+    }
+
+    #[test]
+    fn get_is_bounded() {
+        let mut grid = CellGrid::from_fn(uvec2(3, 2), numbered);
+        assert_eq!(grid.get(ivec2(1, 1)), Some(&11.));
+        for outside in [ivec2(-1, 0), ivec2(3, 0), ivec2(0, 2), ivec2(0, -1)] {
+            assert_eq!(grid.get(outside), None);
+            assert!(grid.get_mut(outside).is_none());
+            assert!(matches!(
+                grid.try_get(outside),
+                Err(Error::OutOfBounds { .. })
+            ));
+        }
+        *grid.get_mut(ivec2(2, 0)).unwrap() = -1.;
+        assert_eq!(grid[uvec2(2, 0)], -1.);
+    }
+
+    #[test]
+    fn clamping_and_wrapping() {
+        let grid = CellGrid::from_fn(uvec2(3, 2), numbered);
+        assert_eq!(*grid.clamped(ivec2(-5, 7)), 10.);
+        assert_eq!(*grid.clamped(ivec2(4, -1)), 2.);
+        assert_eq!(*grid.wrapped(ivec2(-1, 2)), 2.);
+        assert_eq!(*grid.wrapped(ivec2(3, -1)), 10.);
+        assert_eq!(
+            *grid.wrapped_or_clamped(ivec2(-1, 5), BVec2::new(true, false)),
+            12.
+        );
+        assert_eq!(
+            *grid.wrapped_or_clamped(ivec2(-1, 5), BVec2::new(false, true)),
+            10.
+        );
+    }
+
+    #[test]
+    fn face_grids_wrap_over_the_cells() {
+        let faces = FaceGrid::from_fn(Axis::X, uvec2(3, 2), numbered);
+        assert_eq!(faces.dimensions(), uvec2(4, 2));
+        assert_eq!(faces.cell_dimensions(), uvec2(3, 2));
+        assert_eq!(faces.offset(), Vector::new(0., 0.5));
+        // The face at the right border is the one at the left border again.
+        assert_eq!(*faces.wrapped(ivec2(3, 0)), 0.);
+        assert_eq!(*faces.wrapped(ivec2(-1, 1)), 12.);
+        assert!(faces.is_boundary(uvec2(0, 1)));
+        assert!(faces.is_boundary(uvec2(3, 0)));
+        assert!(!faces.is_boundary(uvec2(1, 0)));
+
+        let faces = FaceGrid::new(Axis::Y, uvec2(3, 2), 0.);
+        assert_eq!(faces.dimensions(), uvec2(3, 3));
+        assert_eq!(faces.position(uvec2(1, 2)), Vector::new(1.5, 2.));
+    }
+
+    #[test]
+    fn sampling_is_bilinear() {
+        let grid = CellGrid::from_fn(uvec2(3, 2), numbered);
+        // At the entries themselves.
+        for index in grid.indices() {
+            assert_eq!(grid.sample(grid.position(index)), grid[index]);
+        }
+        // Between them, linear in both directions.
+        assert_eq!(grid.sample(Vector::new(1., 1.)), 5.5);
+        assert_eq!(grid.sample(Vector::new(1.25, 0.5)), 0.75);
+        // Clamped beyond the outermost entries.
+        assert_eq!(grid.sample(Vector::new(-3., 0.5)), 0.);
+        assert_eq!(grid.sample(Vector::new(2.75, 1.75)), 12.);
+        // Wrapped halfway between the last and the first column.
+        assert_eq!(grid.sample_wrapped(Vector::new(3., 0.5)), 1.);
+    }
+
+    #[test]
+    fn sampling_at_faces_and_edges() {
+        let faces = FaceGrid::from_fn(Axis::X, uvec2(3, 2), numbered);
+        assert_eq!(faces.sample(Vector::new(3., 1.5)), 13.);
+        assert_eq!(faces.sample(Vector::new(0.5, 0.5)), 0.5);
+        assert_eq!(faces.sample(Vector::new(1., 1.)), 6.);
+        // Below the lowest face centres the bottom row is used.
+        assert_eq!(faces.sample(Vector::new(2., 0.)), 2.);
+        assert_eq!(faces.sample(Vector::new(3., 2.)), 13.);
+    }
+}
@@ -0,0 +1,226 @@
+//! Colour mapped scalar fields, drawn by the viewer under the velocities.
+
+use std::{fmt, str::FromStr};
+
+use glam::{vec3, Vec3};
+
+use crate::{
+    float::{to_f32, Float},
+    grid::CellGrid,
+    simulation::Simulation,
+};
+
+/// A scalar field per cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scalar {
+    Pressure,
+    Divergence,
+    Vorticity,
+    /// Length of the velocity at the cell centre.
+    Speed,
+    Dye,
+}
+
+impl Scalar {
+    pub const ALL: [Scalar; 5] = [
+        Scalar::Pressure,
+        Scalar::Divergence,
+        Scalar::Vorticity,
+        Scalar::Speed,
+        Scalar::Dye,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Scalar::Pressure => "pressure",
+            Scalar::Divergence => "divergence",
+            Scalar::Vorticity => "vorticity",
+            Scalar::Speed => "speed",
+            Scalar::Dye => "dye",
+        }
+    }
+
+    pub fn field(self, simulation: &Simulation) -> CellGrid<Float> {
+        match self {
+            Scalar::Pressure => simulation.pressures.clone(),
+            Scalar::Divergence => simulation.divergence(),
+            Scalar::Vorticity => simulation.vorticity(),
+            Scalar::Speed => CellGrid::from_fn(simulation.dimensions, |cell| {
+                let position = simulation.pressures.position(cell) * simulation.cell_size;
+                simulation.interpolate_velocity(position).length()
+            }),
+            Scalar::Dye => simulation.dye.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Colormap {
+    /// Perceptually uniform from dark blue over green to yellow.
+    #[default]
+    Viridis,
+    /// Diverging from blue over grey to red, for signed fields.
+    Coolwarm,
+    Grayscale,
+}
+
+/// Viridis at nine evenly spaced points.
+const VIRIDIS: [u32; 9] = [
+    0x440154, 0x472d7b, 0x3b528b, 0x2c728e, 0x21918c, 0x28ae80, 0x5ec962, 0xaddc30, 0xfde725,
+];
+
+/// Moreland's smooth cool to warm map at five evenly spaced points.
+const COOLWARM: [u32; 5] = [0x3b4cc0, 0x8db0fe, 0xdddddd, 0xf49a7b, 0xb40426];
+
+impl Colormap {
+    pub const ALL: [Colormap; 3] = [Colormap::Viridis, Colormap::Coolwarm, Colormap::Grayscale];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Colormap::Viridis => "viridis",
+            Colormap::Coolwarm => "coolwarm",
+            Colormap::Grayscale => "grayscale",
+        }
+    }
+
+    /// The colour at `t` from zero to one, clamped to that range.
+    pub fn color(self, t: Float) -> Vec3 {
+        let t = if t.is_nan() {
+            0.
+        } else {
+            to_f32(t).clamp(0., 1.)
+        };
+        match self {
+            Colormap::Viridis => interpolate(&VIRIDIS, t),
+            Colormap::Coolwarm => interpolate(&COOLWARM, t),
+            Colormap::Grayscale => Vec3::splat(t),
+        }
+    }
+}
+
+/// Linear interpolation between evenly spaced `0xrrggbb` colours.
+fn interpolate(stops: &[u32], t: f32) -> Vec3 {
+    let rgb = |stop: u32| {
+        vec3(
+            (stop >> 16) as f32,
+            (stop >> 8 & 0xff) as f32,
+            (stop & 0xff) as f32,
+        ) / 255.
+    };
+    let scaled = t * (stops.len() - 1) as f32;
+    let index = (scaled as usize).min(stops.len() - 2);
+    rgb(stops[index]).lerp(rgb(stops[index + 1]), scaled - index as f32)
+}
+
+/// The values mapped to the ends of the colour map.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Range {
+    /// From the smallest to the largest finite value of every frame.
+    #[default]
+    Auto,
+    Fixed {
+        min: Float,
+        max: Float,
+    },
+}
+
+impl Range {
+    /// The `[min, max]` of `values`, widened around a single value.
+    pub fn limits(self, values: &[Float]) -> [Float; 2] {
+        let [min, max] = match self {
+            Range::Auto => values.iter().filter(|value| value.is_finite()).fold(
+                [Float::INFINITY, Float::NEG_INFINITY],
+                |[min, max], &value| [min.min(value), max.max(value)],
+            ),
+            Range::Fixed { min, max } => [min, max],
+        };
+        if min > max {
+            [0., 1.]
+        } else if min == max {
+            [min - 0.5, max + 0.5]
+        } else {
+            [min, max]
+        }
+    }
+}
+
+/// A scalar field with the way it is coloured.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Heatmap {
+    pub scalar: Scalar,
+    pub colormap: Colormap,
+    pub range: Range,
+}
+
+impl Heatmap {
+    /// The colour of every cell and the limits of the range they span.
+    pub fn colors(&self, simulation: &Simulation) -> (CellGrid<Vec3>, [Float; 2]) {
+        let field = self.scalar.field(simulation);
+        let [min, max] = self.range.limits(field.as_slice());
+        let colors = CellGrid::from_fn(field.dimensions(), |cell| {
+            self.colormap.color((field[cell] - min) / (max - min))
+        });
+        (colors, [min, max])
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError(pub(crate) String);
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl FromStr for Scalar {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        Scalar::ALL
+            .into_iter()
+            .find(|scalar| scalar.name() == s)
+            .ok_or_else(|| {
+                ParseError(format!(
+                    "unknown field {s:?}, expected one of {}",
+                    Scalar::ALL.map(Scalar::name).join(", ")
+                ))
+            })
+    }
+}
+
+impl FromStr for Colormap {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        Colormap::ALL
+            .into_iter()
+            .find(|colormap| colormap.name() == s)
+            .ok_or_else(|| {
+                ParseError(format!(
+                    "unknown colour map {s:?}, expected one of {}",
+                    Colormap::ALL.map(Colormap::name).join(", ")
+                ))
+            })
+    }
+}
+
+/// `auto` or `min,max`.
+impl FromStr for Range {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        if s == "auto" {
+            return Ok(Range::Auto);
+        }
+        let invalid = || ParseError(format!("expected `auto` or `min,max`, found {s:?}"));
+        let (min, max) = s.split_once(',').ok_or_else(invalid)?;
+        let [min, max] = [min, max].map(|limit| limit.trim().parse::<Float>());
+        match (min, max) {
+            (Ok(min), Ok(max)) if min < max => Ok(Range::Fixed { min, max }),
+            _ => Err(invalid()),
+        }
+    }
+}
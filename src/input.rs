@@ -0,0 +1,353 @@
+//! Inputs of the viewer, recorded with the frame they arrived in so that a
+//! session can be replayed, with or without a window.
+//!
+//! A recording is a text file with a header line followed by one input per
+//! line, each prefixed with its frame number:
+//!
+//! ```text
+//! mac_grid_fluid inputs 1 3c1f0e5d2a7b9c84
+//! 0 run play
+//! 12 cursor 310 164 4 -2
+//! 40 show staggered
+//! 95 quit
+//! ```
+//!
+//! The header carries the [`Simulation::state_hash`] of the initial state,
+//! which tells whether a replay starts where the recording did. Replays are
+//! only bitwise faithful for [`Simulation::deterministic`] simulations.
+
+use std::{
+    collections::VecDeque,
+    error, fmt,
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::Path,
+};
+
+use crate::{
+    float::{Float, Vector},
+    heatmap::Scalar,
+    simulation::Simulation,
+};
+
+const MAGIC: &str = "mac_grid_fluid inputs";
+
+/// Bumped whenever the header changes. It does not cover the kinds of
+/// input, a build that does not know a kind fails on its line with
+/// [`InputError::Line`].
+const VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunMode {
+    /// Advance only on [`Input::Step`].
+    Step,
+    /// Advance every frame.
+    Play,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VelocityMode {
+    /// Only particles.
+    Hide,
+    /// One arrow per cell.
+    Combined,
+    /// One arrow per face.
+    Staggered,
+    /// Lines along the current velocities through the seeds.
+    Streamlines,
+    /// Trajectories of particles released from the seeds.
+    Pathlines,
+    /// Particles released from the seeds at every step.
+    Streaklines,
+    /// Noise smeared along the streamlines, see [`crate::lic`].
+    Lic,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Input {
+    /// The cursor moved to `position`, in world units, dragging the fluid
+    /// along `velocity` while the left button is held and zero otherwise.
+    Cursor {
+        position: Vector,
+        velocity: Vector,
+    },
+    /// A particle was dropped at this position.
+    Particle(Vector),
+    /// Lines start from this position too.
+    Seed(Vector),
+    Run(RunMode),
+    Show(VelocityMode),
+    /// Colour the cells by a field, or not at all.
+    Heatmap(Option<Scalar>),
+    /// Advance a single step.
+    Step,
+    /// Save the simulation state to the snapshot file.
+    Save,
+    /// Replace the simulation state with the snapshot file.
+    Load,
+    Quit,
+}
+
+/// The state that inputs leave behind from frame to frame and that decides
+/// how the simulation advances.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Controls {
+    pub run_mode: RunMode,
+    pub velocity_mode: VelocityMode,
+    pub heatmap: Option<Scalar>,
+    /// Cursor position in world units.
+    pub cursor: Vector,
+    /// Drag of the cursor, pointing up until it first moves.
+    pub velocity: Vector,
+    /// Radius of the cursor's push, in cells.
+    pub interaction_radius: Float,
+}
+
+impl Controls {
+    pub fn new(interaction_radius: Float) -> Self {
+        Self {
+            run_mode: RunMode::Step,
+            velocity_mode: VelocityMode::Combined,
+            heatmap: None,
+            cursor: Vector::ZERO,
+            velocity: Vector::Y,
+            interaction_radius,
+        }
+    }
+
+    /// Takes over the cursor and modes, other inputs are up to the caller.
+    pub fn apply(&mut self, input: &Input) {
+        match *input {
+            Input::Cursor { position, velocity } => {
+                self.cursor = position;
+                self.velocity = velocity;
+            }
+            Input::Run(run_mode) => self.run_mode = run_mode,
+            Input::Show(velocity_mode) => self.velocity_mode = velocity_mode,
+            Input::Heatmap(heatmap) => self.heatmap = heatmap,
+            _ => {}
+        }
+    }
+
+    /// Ends a frame: while playing, pushes the fluid at the cursor and steps,
+    /// otherwise steps only if asked to. Returns whether it stepped.
+    pub fn advance(&self, simulation: &mut Simulation, step: bool) -> bool {
+        if self.run_mode == RunMode::Play {
+            simulation.interact(
+                self.cursor,
+                self.velocity.clamp(Vector::NEG_ONE, Vector::ONE),
+                self.interaction_radius * simulation.cell_size,
+            );
+        }
+        if self.run_mode == RunMode::Play || step {
+            simulation.step();
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum InputError {
+    Io(io::Error),
+    /// Not a recording of inputs, or one written by a newer version.
+    Header(String),
+    /// A line that is not an input.
+    Line {
+        line: usize,
+        text: String,
+    },
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputError::Io(err) => write!(f, "could not read inputs: {err}"),
+            InputError::Header(header) => write!(f, "not a recording of inputs: {header:?}"),
+            InputError::Line { line, text } => write!(f, "invalid input on line {line}: {text:?}"),
+        }
+    }
+}
+
+impl error::Error for InputError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            InputError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for InputError {
+    fn from(err: io::Error) -> Self {
+        InputError::Io(err)
+    }
+}
+
+/// Appends inputs to a recording, one line per call to
+/// [`InputRecorder::record`].
+pub struct InputRecorder<W: Write> {
+    writer: W,
+}
+
+impl InputRecorder<BufWriter<File>> {
+    pub fn create(path: impl AsRef<Path>, initial: &Simulation) -> io::Result<Self> {
+        Self::new(BufWriter::new(File::create(path)?), initial)
+    }
+}
+
+impl<W: Write> InputRecorder<W> {
+    /// Writes the header with the hash of the `initial` state right away.
+    pub fn new(mut writer: W, initial: &Simulation) -> io::Result<Self> {
+        writeln!(writer, "{MAGIC} {VERSION} {:016x}", initial.state_hash())?;
+        Ok(Self { writer })
+    }
+
+    pub fn record(&mut self, frame: u64, input: &Input) -> io::Result<()> {
+        write!(self.writer, "{frame} ")?;
+        match input {
+            Input::Cursor { position, velocity } => writeln!(
+                self.writer,
+                "cursor {} {} {} {}",
+                position.x, position.y, velocity.x, velocity.y
+            ),
+            Input::Particle(position) => {
+                writeln!(self.writer, "particle {} {}", position.x, position.y)
+            }
+            Input::Seed(position) => writeln!(self.writer, "seed {} {}", position.x, position.y),
+            Input::Run(run_mode) => writeln!(
+                self.writer,
+                "run {}",
+                match run_mode {
+                    RunMode::Step => "step",
+                    RunMode::Play => "play",
+                }
+            ),
+            Input::Show(velocity_mode) => writeln!(
+                self.writer,
+                "show {}",
+                match velocity_mode {
+                    VelocityMode::Hide => "hide",
+                    VelocityMode::Combined => "combined",
+                    VelocityMode::Staggered => "staggered",
+                    VelocityMode::Streamlines => "streamlines",
+                    VelocityMode::Pathlines => "pathlines",
+                    VelocityMode::Streaklines => "streaklines",
+                    VelocityMode::Lic => "lic",
+                }
+            ),
+            Input::Heatmap(heatmap) => writeln!(
+                self.writer,
+                "heatmap {}",
+                heatmap.map_or("off", Scalar::name)
+            ),
+            Input::Step => writeln!(self.writer, "step"),
+            Input::Save => writeln!(self.writer, "save"),
+            Input::Load => writeln!(self.writer, "load"),
+            Input::Quit => writeln!(self.writer, "quit"),
+        }
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Inputs read back from a recording, handed out frame by frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replay {
+    /// Hash of the state the recording started from.
+    pub initial_hash: u64,
+    inputs: VecDeque<(u64, Input)>,
+}
+
+impl Replay {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, InputError> {
+        Self::read(BufReader::new(File::open(path)?))
+    }
+
+    pub fn read(reader: impl BufRead) -> Result<Self, InputError> {
+        let mut lines = reader.lines();
+        let header = lines.next().transpose()?.unwrap_or_default();
+        let initial_hash = header
+            .strip_prefix(MAGIC)
+            .and_then(
+                |rest| match rest.split_whitespace().collect::<Vec<_>>()[..] {
+                    [version, hash] if version.parse() == Ok(VERSION) => {
+                        u64::from_str_radix(hash, 16).ok()
+                    }
+                    _ => None,
+                },
+            )
+            .ok_or(InputError::Header(header.clone()))?;
+
+        let mut inputs = VecDeque::new();
+        for (index, line) in lines.enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let input = parse(&line).ok_or_else(|| InputError::Line {
+                line: index + 2,
+                text: line.clone(),
+            })?;
+            inputs.push_back(input);
+        }
+        Ok(Self {
+            initial_hash,
+            inputs,
+        })
+    }
+
+    /// Takes the inputs of `frame` and any earlier ones not taken yet.
+    pub fn frame(&mut self, frame: u64) -> Vec<Input> {
+        let count = self
+            .inputs
+            .iter()
+            .take_while(|(recorded, _)| *recorded <= frame)
+            .count();
+        self.inputs.drain(..count).map(|(_, input)| input).collect()
+    }
+
+    /// Whether all inputs have been taken.
+    pub fn is_finished(&self) -> bool {
+        self.inputs.is_empty()
+    }
+
+    /// Frame of the last input, the length of the recording.
+    pub fn last_frame(&self) -> Option<u64> {
+        self.inputs.back().map(|(frame, _)| *frame)
+    }
+}
+
+fn parse(line: &str) -> Option<(u64, Input)> {
+    let words = line.split_whitespace().collect::<Vec<_>>();
+    let (frame, words) = words.split_first()?;
+    let vector = |x: &str, y: &str| Some(Vector::new(x.parse().ok()?, y.parse().ok()?));
+    let input = match words {
+        ["cursor", x, y, velocity_x, velocity_y] => Input::Cursor {
+            position: vector(x, y)?,
+            velocity: vector(velocity_x, velocity_y)?,
+        },
+        ["particle", x, y] => Input::Particle(vector(x, y)?),
+        ["seed", x, y] => Input::Seed(vector(x, y)?),
+        ["run", "step"] => Input::Run(RunMode::Step),
+        ["run", "play"] => Input::Run(RunMode::Play),
+        ["show", "hide"] => Input::Show(VelocityMode::Hide),
+        ["show", "combined"] => Input::Show(VelocityMode::Combined),
+        ["show", "staggered"] => Input::Show(VelocityMode::Staggered),
+        ["show", "streamlines"] => Input::Show(VelocityMode::Streamlines),
+        ["show", "pathlines"] => Input::Show(VelocityMode::Pathlines),
+        ["show", "streaklines"] => Input::Show(VelocityMode::Streaklines),
+        ["show", "lic"] => Input::Show(VelocityMode::Lic),
+        ["heatmap", "off"] => Input::Heatmap(None),
+        ["heatmap", scalar] => Input::Heatmap(Some(scalar.parse().ok()?)),
+        ["step"] => Input::Step,
+        ["save"] => Input::Save,
+        ["load"] => Input::Load,
+        ["quit"] => Input::Quit,
+        _ => return None,
+    };
+    Some((frame.parse().ok()?, input))
+}
@@ -0,0 +1,57 @@
+//! Euler fluid simulation on a staggered MAC grid.
+//!
+//! Pressures live at cell centres and velocities on the cell faces normal to
+//! their component, see [`CellGrid`] and [`FaceGrid`]. A [`Simulation`] is
+//! advanced with [`Simulation::step`] and can be inspected through its public
+//! fields or the [`Cell`] iterators used by the viewer. A [`Scene`] describes
+//! the setup of a simulation and is loaded from TOML files, while
+//! [`Simulation::save`] and [`Simulation::load`] store and restore its state.
+//! Fields can be exported for ParaView with the [`vtk`] module and for NumPy
+//! with the [`npy`] module, and statistics recorded over time with the
+//! [`recorder`] module. The [`input`] module records the viewer's inputs so
+//! that sessions can be replayed.
+//!
+//! The simulation runs in `f32`, or in `f64` with the `f64` feature, see the
+//! [`float`] module.
+//!
+//! ```
+//! use glam::uvec2;
+//! use mac_grid_fluid::{Simulation, Vector};
+//!
+//! let mut simulation = Simulation::new(uvec2(60, 30), 20., 0.5)?;
+//! simulation.interact(Vector::new(600., 300.), Vector::new(1., 0.), 60.);
+//! simulation.step();
+//! let velocity = simulation.interpolate_velocity(Vector::new(600., 300.));
+//! # let _ = velocity;
+//! # Ok::<(), mac_grid_fluid::Error>(())
+//! ```
+
+mod boundary;
+mod diagnostics;
+mod error;
+pub mod float;
+pub mod grid;
+pub mod heatmap;
+pub mod input;
+pub mod lic;
+pub mod lines;
+pub mod npy;
+pub mod particles;
+pub mod recorder;
+mod reduce;
+mod region;
+pub mod scene;
+mod simulation;
+mod snapshot;
+mod stencil;
+pub mod vtk;
+
+pub use boundary::{Boundaries, Boundary, WallVelocities};
+pub use diagnostics::Diagnostics;
+pub use error::Error;
+pub use float::{Float, Vector};
+pub use grid::{Axis, CellGrid, FaceGrid, Grid};
+pub use region::{Emitter, Region};
+pub use scene::{Scene, SceneError};
+pub use simulation::{Cell, PressureSolver, Simulation, SolverOptions};
+pub use snapshot::SnapshotError;
@@ -0,0 +1,109 @@
+//! Line integral convolution: white noise averaged along the streamlines of
+//! the current velocities, a dense picture of where the flow goes.
+//!
+//! The image has `resolution` texels per cell along each axis and is meant
+//! to be recomputed every few frames, as every texel traces a streamline.
+
+use glam::{UVec2, Vec3};
+
+use crate::{
+    float::{to_f32, Float, Vector},
+    grid::CellGrid,
+    heatmap::{Colormap, Range},
+    lines::streamline,
+    simulation::Simulation,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lic {
+    /// Texels per cell along each axis.
+    pub resolution: u32,
+    /// Length of the streamline on either side of a texel, in cells.
+    pub length: Float,
+    noise: CellGrid<Float>,
+}
+
+impl Lic {
+    /// Noise for a grid of `dimensions` cells, the same for the same `seed`.
+    /// Panics on empty `dimensions` or a zero `resolution`.
+    pub fn new(dimensions: UVec2, resolution: u32, length: Float, seed: u64) -> Self {
+        let texels = dimensions * resolution;
+        Self {
+            resolution,
+            length,
+            noise: CellGrid::from_fn(texels, |texel| {
+                noise(seed, texel.y as u64 * texels.x as u64 + texel.x as u64)
+            }),
+        }
+    }
+
+    /// Texels along each axis.
+    pub fn dimensions(&self) -> UVec2 {
+        self.noise.dimensions()
+    }
+
+    /// Centre of `texel` in world units.
+    pub fn position(&self, simulation: &Simulation, texel: UVec2) -> Vector {
+        self.noise.position(texel) * self.texel_size(simulation)
+    }
+
+    fn texel_size(&self, simulation: &Simulation) -> Float {
+        simulation.cell_size / self.resolution as Float
+    }
+
+    /// The mean noise along the streamline through every texel, zero in
+    /// solids. Parallel with the `parallel` feature.
+    pub fn image(&self, simulation: &Simulation) -> CellGrid<Float> {
+        let step = 1. / self.resolution as Float;
+        let points = (self.length * self.resolution as Float).ceil() as usize;
+        let texel_size = self.texel_size(simulation);
+        CellGrid::par_from_fn(self.dimensions(), |texel| {
+            let line = streamline(simulation, self.position(simulation, texel), step, points);
+            if line.is_empty() {
+                return 0.;
+            }
+            let sum = line
+                .iter()
+                .map(|&point| {
+                    let texel = (point / texel_size).floor().as_ivec2();
+                    *self.noise.clamped(texel)
+                })
+                .sum::<Float>();
+            sum / line.len() as Float
+        })
+    }
+
+    /// The image in grey, or coloured by the speed at every texel with
+    /// `tint`, stretched to span from black to full brightness.
+    pub fn colors(&self, simulation: &Simulation, tint: Option<Colormap>) -> CellGrid<Vec3> {
+        let image = self.image(simulation);
+        let [min, max] = Range::Auto.limits(image.as_slice());
+        let speeds = tint.map(|colormap| {
+            let speeds = CellGrid::par_from_fn(self.dimensions(), |texel| {
+                simulation
+                    .interpolate_velocity(self.position(simulation, texel))
+                    .length()
+            });
+            let limits = Range::Auto.limits(speeds.as_slice());
+            (colormap, speeds, limits)
+        });
+        CellGrid::from_fn(self.dimensions(), |texel| {
+            let brightness = to_f32((image[texel] - min) / (max - min)).clamp(0., 1.);
+            match &speeds {
+                Some((colormap, speeds, [min, max])) => {
+                    brightness * colormap.color((speeds[texel] - min) / (max - min))
+                }
+                None => Vec3::splat(brightness),
+            }
+        })
+    }
+}
+
+/// Uniform noise from zero to one, from the SplitMix64 finaliser.
+fn noise(seed: u64, index: u64) -> Float {
+    let mut z = seed.wrapping_add(index.wrapping_add(1).wrapping_mul(0x9e3779b97f4a7c15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^= z >> 31;
+    (z >> 40) as Float / (1u64 << 24) as Float
+}
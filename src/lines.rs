@@ -0,0 +1,146 @@
+//! Streamlines, pathlines and streaklines of a simulation, as polylines of
+//! positions in world units.
+//!
+//! Streamlines follow the current velocities, pathlines the trajectories of
+//! particles released once and streaklines connect the particles released
+//! from a seed at every step. Lines end where they leave the domain, also
+//! across periodic sides, or run into solids.
+
+use std::collections::VecDeque;
+
+use crate::{
+    float::{Float, Vector},
+    simulation::Simulation,
+};
+
+/// Speeds below this, in cells per unit of time, end streamlines.
+const STAGNANT: Float = 1e-6;
+
+/// Whether the fluid can carry a particle at `position` in world units.
+pub fn is_inside(simulation: &Simulation, position: Vector) -> bool {
+    let normalized = (position / simulation.cell_size).floor();
+    normalized.is_finite()
+        && simulation
+            .solids
+            .get(normalized.as_ivec2())
+            .is_some_and(|solid| !solid)
+}
+
+/// The streamline through `seed`, traced up- and downstream with midpoint
+/// steps of `step` cells, with up to `max_points` on either side.
+pub fn streamline(
+    simulation: &Simulation,
+    seed: Vector,
+    step: Float,
+    max_points: usize,
+) -> Vec<Vector> {
+    if !is_inside(simulation, seed) {
+        return Vec::new();
+    }
+    let length = step * simulation.cell_size;
+    let direction = |position: Vector| {
+        let velocity = simulation.interpolate_velocity(position);
+        (velocity.length() > STAGNANT * simulation.cell_size).then(|| velocity.normalize())
+    };
+    let trace = |sign: Float| {
+        let mut points = Vec::new();
+        let mut position = seed;
+        while points.len() < max_points {
+            let Some(midpoint) =
+                direction(position).map(|direction| position + 0.5 * sign * length * direction)
+            else {
+                break;
+            };
+            let Some(direction) = direction(midpoint) else {
+                break;
+            };
+            position += sign * length * direction;
+            if !is_inside(simulation, position) {
+                break;
+            }
+            points.push(position);
+        }
+        points
+    };
+
+    let mut line = trace(-1.);
+    line.reverse();
+    line.push(seed);
+    line.extend(trace(1.));
+    line
+}
+
+/// Moves a particle at `position` along the velocities for one time step
+/// with a midpoint step.
+pub(crate) fn advect(simulation: &Simulation, position: Vector) -> Vector {
+    let midpoint =
+        position + 0.5 * simulation.time_step * simulation.interpolate_velocity(position);
+    position + simulation.time_step * simulation.interpolate_velocity(midpoint)
+}
+
+/// Trajectories of one particle per seed, growing with every step until
+/// they leave the fluid or reach `max_points`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pathlines {
+    pub lines: Vec<Vec<Vector>>,
+    pub max_points: usize,
+}
+
+impl Pathlines {
+    pub fn new(seeds: &[Vector], max_points: usize) -> Self {
+        Self {
+            lines: seeds.iter().map(|&seed| vec![seed]).collect(),
+            max_points,
+        }
+    }
+
+    /// Extends every line by one step, up to the first point outside the
+    /// fluid.
+    pub fn advance(&mut self, simulation: &Simulation) {
+        for line in &mut self.lines {
+            let Some(&head) = line.last() else {
+                continue;
+            };
+            if line.len() < self.max_points && is_inside(simulation, head) {
+                line.push(advect(simulation, head));
+            }
+        }
+    }
+}
+
+/// Particles released from each seed at every step, newest first, up to
+/// `max_points` per seed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Streaklines {
+    pub seeds: Vec<Vector>,
+    pub lines: Vec<VecDeque<Vector>>,
+    pub max_points: usize,
+}
+
+impl Streaklines {
+    pub fn new(seeds: &[Vector], max_points: usize) -> Self {
+        Self {
+            seeds: seeds.to_vec(),
+            lines: seeds.iter().map(|&seed| VecDeque::from([seed])).collect(),
+            max_points,
+        }
+    }
+
+    /// Moves the released particles, drops those that left the fluid along
+    /// with all released before them, and releases new ones.
+    pub fn advance(&mut self, simulation: &Simulation) {
+        for (seed, line) in self.seeds.iter().zip(&mut self.lines) {
+            for position in line.iter_mut() {
+                *position = advect(simulation, *position);
+            }
+            if let Some(lost) = line
+                .iter()
+                .position(|&position| !is_inside(simulation, position))
+            {
+                line.truncate(lost);
+            }
+            line.push_front(*seed);
+            line.truncate(self.max_points);
+        }
+    }
+}
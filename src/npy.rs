@@ -0,0 +1,135 @@
+//! Export to NumPy `.npy` arrays and `.npz` archives.
+//!
+//! Grids are stored in row-major order, so a grid of `dimensions` entries
+//! becomes an array of shape `(dimensions.y, dimensions.x)` and indexing it
+//! with `[j, i]` in Python matches `grid[uvec2(i, j)]`.
+
+use std::{
+    io::{self, Write},
+    mem,
+};
+
+use crate::{float::Float, grid::Grid, simulation::Simulation};
+
+/// The exported fields of a simulation by name, with their own shapes:
+/// cells for `pressures`, `dye`, `divergence` and `vorticity`, one more
+/// column for `velocities_x` and one more row for `velocities_y`.
+pub fn fields(simulation: &Simulation) -> Vec<(&'static str, Grid<Float>)> {
+    vec![
+        ("pressures", Grid::clone(&simulation.pressures)),
+        ("velocities_x", Grid::clone(&simulation.velocities_x)),
+        ("velocities_y", Grid::clone(&simulation.velocities_y)),
+        ("dye", Grid::clone(&simulation.dye)),
+        ("divergence", Grid::clone(&simulation.divergence())),
+        ("vorticity", Grid::clone(&simulation.vorticity())),
+    ]
+}
+
+/// Writes a single `.npy` array of little endian [`Float`]s.
+pub fn write_npy(mut writer: impl Write, grid: &Grid<Float>) -> io::Result<()> {
+    writer.write_all(&npy(grid))?;
+    writer.flush()
+}
+
+/// Writes an uncompressed `.npz` archive with one `{name}.npy` per array, as
+/// read by `numpy.load`.
+pub fn write_npz(mut writer: impl Write, arrays: &[(&str, Grid<Float>)]) -> io::Result<()> {
+    let mut central_directory = Vec::new();
+    let mut offset = 0;
+
+    for (name, grid) in arrays {
+        let name = format!("{name}.npy");
+        let data = npy(grid);
+        let crc = crc32(&data);
+
+        let mut local_header = Vec::new();
+        put_u32(&mut local_header, 0x04034b50);
+        put_entry_info(&mut local_header, crc, data.len(), name.len());
+        local_header.extend_from_slice(name.as_bytes());
+        writer.write_all(&local_header)?;
+        writer.write_all(&data)?;
+
+        put_u32(&mut central_directory, 0x02014b50);
+        put_u16(&mut central_directory, 20);
+        put_entry_info(&mut central_directory, crc, data.len(), name.len());
+        // Comment length, disk number and internal and external attributes.
+        put_u16(&mut central_directory, 0);
+        put_u16(&mut central_directory, 0);
+        put_u16(&mut central_directory, 0);
+        put_u32(&mut central_directory, 0);
+        put_u32(&mut central_directory, offset as u32);
+        central_directory.extend_from_slice(name.as_bytes());
+
+        offset += local_header.len() + data.len();
+    }
+
+    let mut end = Vec::new();
+    put_u32(&mut end, 0x06054b50);
+    put_u16(&mut end, 0);
+    put_u16(&mut end, 0);
+    put_u16(&mut end, arrays.len() as u16);
+    put_u16(&mut end, arrays.len() as u16);
+    put_u32(&mut end, central_directory.len() as u32);
+    put_u32(&mut end, offset as u32);
+    put_u16(&mut end, 0);
+
+    writer.write_all(&central_directory)?;
+    writer.write_all(&end)?;
+    writer.flush()
+}
+
+fn npy(grid: &Grid<Float>) -> Vec<u8> {
+    let dimensions = grid.dimensions();
+    let mut header = format!(
+        "{{'descr': '<f{}', 'fortran_order': False, 'shape': ({}, {}), }}",
+        mem::size_of::<Float>(),
+        dimensions.y,
+        dimensions.x,
+    );
+    // Magic, version and header length take 10 bytes, the header is padded
+    // with spaces and a newline to align the data to 64 bytes.
+    let padding = 63 - (10 + header.len()) % 64;
+    header.extend(std::iter::repeat_n(' ', padding));
+    header.push('\n');
+
+    let mut bytes = Vec::with_capacity(10 + header.len() + mem::size_of::<Float>() * grid.len());
+    bytes.extend_from_slice(b"\x93NUMPY\x01\x00");
+    put_u16(&mut bytes, header.len() as u16);
+    bytes.extend_from_slice(header.as_bytes());
+    for value in grid.iter() {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    bytes
+}
+
+/// The part shared by local file headers and central directory entries of a
+/// stored, i.e. uncompressed, zip entry.
+fn put_entry_info(bytes: &mut Vec<u8>, crc: u32, size: usize, name_len: usize) {
+    // Version needed, flags, compression, time and date (1980-01-01).
+    put_u16(bytes, 20);
+    put_u16(bytes, 0);
+    put_u16(bytes, 0);
+    put_u16(bytes, 0);
+    put_u16(bytes, 0x21);
+    put_u32(bytes, crc);
+    put_u32(bytes, size as u32);
+    put_u32(bytes, size as u32);
+    put_u16(bytes, name_len as u16);
+    put_u16(bytes, 0);
+}
+
+fn put_u16(bytes: &mut Vec<u8>, value: u16) {
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u32(bytes: &mut Vec<u8>, value: u32) {
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0, |crc, &byte| {
+        (0..8).fold(crc ^ byte as u32, |crc, _| {
+            (crc >> 1) ^ (0xedb88320 & (crc & 1).wrapping_neg())
+        })
+    })
+}
@@ -0,0 +1,218 @@
+//! Particles carried along by the flow, released by emitters or by hand.
+//!
+//! Particles age with every step and are removed once they outlive their
+//! lifetime, leave the domain or enter a solid. Each one keeps a trail of
+//! its last positions.
+
+use std::{collections::VecDeque, str::FromStr};
+
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    float::{Float, Vector},
+    heatmap::{Colormap, ParseError, Range},
+    lines::{advect, is_inside},
+    simulation::Simulation,
+};
+
+/// Where an emitter releases particles, in cells.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "shape", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Source {
+    Point { position: Vector },
+    Line { start: Vector, end: Vector },
+    Box { min: Vector, max: Vector },
+}
+
+impl Source {
+    /// The `index`-th release, spread evenly over lines and boxes by a
+    /// low-discrepancy sequence rather than at random, so that runs repeat.
+    pub fn position(&self, index: u64) -> Vector {
+        // Roberts' R2 sequence, built on the plastic number.
+        const R2: [f64; 2] = [0.754_877_666_246_693, 0.569_840_290_998_053];
+        let fraction = |alpha: f64| (0.5 + alpha * index as f64).fract() as Float;
+        match *self {
+            Source::Point { position } => position,
+            Source::Line { start, end } => start.lerp(end, fraction(R2[0])),
+            Source::Box { min, max } => {
+                min + (max - min) * Vector::new(fraction(R2[0]), fraction(R2[1]))
+            }
+        }
+    }
+}
+
+/// Releases particles at a steady rate.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ParticleEmitter {
+    pub source: Source,
+    /// Particles per unit of time.
+    pub rate: Float,
+    /// Overrides [`ParticleSettings::lifetime`] if set.
+    #[serde(default)]
+    pub lifetime: Option<Float>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ParticleSettings {
+    /// Time a particle lives, `inf` for ever.
+    pub lifetime: Float,
+    /// Positions kept behind every particle.
+    pub trail: usize,
+    pub emitters: Vec<ParticleEmitter>,
+}
+
+impl Default for ParticleSettings {
+    fn default() -> Self {
+        Self {
+            lifetime: 200.,
+            trail: 16,
+            emitters: Vec::new(),
+        }
+    }
+}
+
+/// A particle in world units.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Particle {
+    pub position: Vector,
+    pub age: Float,
+    pub lifetime: Float,
+    /// Earlier positions, latest first.
+    pub trail: VecDeque<Vector>,
+}
+
+/// What particles are coloured by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ParticleColor {
+    /// From newly released to the end of the lifetime.
+    Age,
+    /// From the slowest to the fastest particle.
+    #[default]
+    Speed,
+}
+
+impl ParticleColor {
+    pub const ALL: [ParticleColor; 2] = [ParticleColor::Age, ParticleColor::Speed];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ParticleColor::Age => "age",
+            ParticleColor::Speed => "speed",
+        }
+    }
+}
+
+impl FromStr for ParticleColor {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        ParticleColor::ALL
+            .into_iter()
+            .find(|color| color.name() == s)
+            .ok_or_else(|| {
+                ParseError(format!(
+                    "unknown particle colour {s:?}, expected one of {}",
+                    ParticleColor::ALL.map(ParticleColor::name).join(", ")
+                ))
+            })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Particles {
+    pub settings: ParticleSettings,
+    pub particles: Vec<Particle>,
+    /// Particles released so far and the fraction of one still owed, per
+    /// emitter.
+    released: Vec<(u64, Float)>,
+}
+
+impl Particles {
+    pub fn new(settings: ParticleSettings) -> Self {
+        Self {
+            released: vec![(0, 0.); settings.emitters.len()],
+            settings,
+            particles: Vec::new(),
+        }
+    }
+
+    /// Drops a particle at `position` in world units.
+    pub fn add(&mut self, position: Vector) {
+        self.particles.push(Particle {
+            position,
+            age: 0.,
+            lifetime: self.settings.lifetime,
+            trail: VecDeque::new(),
+        });
+    }
+
+    /// Removes every particle and restarts the emitters, as if just created.
+    pub fn reset(&mut self) {
+        self.particles.clear();
+        self.released = vec![(0, 0.); self.settings.emitters.len()];
+    }
+
+    /// Moves the particles for one step, removes those that expired or left
+    /// the fluid and then releases new ones.
+    pub fn advance(&mut self, simulation: &Simulation) {
+        let trail = self.settings.trail;
+        for particle in &mut self.particles {
+            particle.trail.push_front(particle.position);
+            particle.trail.truncate(trail);
+            particle.position = advect(simulation, particle.position);
+            particle.age += simulation.time_step;
+        }
+        self.particles.retain(|particle| {
+            particle.age < particle.lifetime && is_inside(simulation, particle.position)
+        });
+
+        for (emitter, (count, owed)) in self.settings.emitters.iter().zip(&mut self.released) {
+            *owed += emitter.rate * simulation.time_step;
+            while *owed >= 1. {
+                *owed -= 1.;
+                let position = emitter.source.position(*count) * simulation.cell_size;
+                *count += 1;
+                if is_inside(simulation, position) {
+                    self.particles.push(Particle {
+                        position,
+                        age: 0.,
+                        lifetime: emitter.lifetime.unwrap_or(self.settings.lifetime),
+                        trail: VecDeque::new(),
+                    });
+                }
+            }
+        }
+    }
+
+    /// The colour of every particle, speeds span the colour map from the
+    /// slowest to the fastest.
+    pub fn colors(
+        &self,
+        simulation: &Simulation,
+        color: ParticleColor,
+        colormap: Colormap,
+    ) -> Vec<Vec3> {
+        match color {
+            ParticleColor::Age => self
+                .particles
+                .iter()
+                .map(|particle| colormap.color(particle.age / particle.lifetime))
+                .collect(),
+            ParticleColor::Speed => {
+                let speeds = self
+                    .particles
+                    .iter()
+                    .map(|particle| simulation.interpolate_velocity(particle.position).length())
+                    .collect::<Vec<_>>();
+                let [min, max] = Range::Auto.limits(&speeds);
+                speeds
+                    .iter()
+                    .map(|speed| colormap.color((speed - min) / (max - min)))
+                    .collect()
+            }
+        }
+    }
+}
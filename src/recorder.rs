@@ -0,0 +1,125 @@
+//! Time series of simulation statistics as CSV or JSON lines, for plotting
+//! how runs evolve and comparing them across versions.
+
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use crate::{diagnostics::divergence_norms, float::Float, simulation::Simulation};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordFormat {
+    /// Comma separated with a header line.
+    Csv,
+    /// One JSON object per line.
+    JsonLines,
+}
+
+impl RecordFormat {
+    /// JSON lines for `.jsonl`, `.ndjson` and `.json` files, CSV otherwise.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("jsonl" | "ndjson" | "json") => RecordFormat::JsonLines,
+            _ => RecordFormat::Csv,
+        }
+    }
+}
+
+/// Statistics of the simulation state after a step.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Record {
+    pub step: u32,
+    /// `step` times the time step.
+    pub time: Float,
+    /// See [`Simulation::kinetic_energy`].
+    pub energy: Float,
+    /// See [`Simulation::enstrophy`].
+    pub enstrophy: Float,
+    /// Root mean square of the divergence over the fluid cells.
+    pub divergence: Float,
+    /// Pressure solver iterations of the last step, zero before the first.
+    pub solver_iterations: u32,
+}
+
+impl Record {
+    pub fn new(simulation: &Simulation, step: u32) -> Self {
+        Self {
+            step,
+            time: step as Float * simulation.time_step,
+            energy: simulation.kinetic_energy(),
+            enstrophy: simulation.enstrophy(),
+            divergence: divergence_norms(simulation).0,
+            solver_iterations: simulation.diagnostics().pressure_iterations,
+        }
+    }
+}
+
+/// Appends one [`Record`] per call to [`Recorder::record`].
+pub struct Recorder<W: Write> {
+    writer: W,
+    format: RecordFormat,
+}
+
+impl Recorder<BufWriter<File>> {
+    /// Creates the file, picking the format from its extension with
+    /// [`RecordFormat::from_path`].
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let writer = BufWriter::new(File::create(path)?);
+        Self::new(writer, RecordFormat::from_path(path))
+    }
+}
+
+impl<W: Write> Recorder<W> {
+    /// Writes the CSV header right away.
+    pub fn new(mut writer: W, format: RecordFormat) -> io::Result<Self> {
+        if format == RecordFormat::Csv {
+            writeln!(
+                writer,
+                "step,time,energy,enstrophy,divergence,solver_iterations"
+            )?;
+        }
+        Ok(Self { writer, format })
+    }
+
+    pub fn record(&mut self, simulation: &Simulation, step: u32) -> io::Result<()> {
+        let Record {
+            step,
+            time,
+            energy,
+            enstrophy,
+            divergence,
+            solver_iterations,
+        } = Record::new(simulation, step);
+
+        match self.format {
+            RecordFormat::Csv => writeln!(
+                self.writer,
+                "{step},{time},{energy},{enstrophy},{divergence},{solver_iterations}"
+            ),
+            RecordFormat::JsonLines => writeln!(
+                self.writer,
+                r#"{{"step":{step},"time":{},"energy":{},"enstrophy":{},"divergence":{},"solver_iterations":{solver_iterations}}}"#,
+                json(time),
+                json(energy),
+                json(enstrophy),
+                json(divergence),
+            ),
+        }
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// JSON has no infinities or NaN, those become `null`.
+fn json(value: Float) -> String {
+    if value.is_finite() {
+        value.to_string()
+    } else {
+        "null".to_string()
+    }
+}
@@ -0,0 +1,33 @@
+//! Reductions over all entries of a field, such as sums for diagnostics.
+//!
+//! With the `parallel` feature they are split across the rayon thread pool,
+//! which combines partial results in an order that depends on the number of
+//! threads and on timing. Floating point addition is not associative, so
+//! sums then differ in the last bits from run to run, unless the simulation
+//! is `deterministic` and they run in storage order.
+
+/// Combines `map` of every index below `len`, starting from `identity`.
+pub(crate) fn reduce<A>(
+    len: usize,
+    deterministic: bool,
+    identity: A,
+    map: impl Fn(usize) -> A + Sync + Send,
+    combine: impl Fn(A, A) -> A + Sync + Send,
+) -> A
+where
+    A: Copy + Send + Sync,
+{
+    #[cfg(feature = "parallel")]
+    if !deterministic {
+        use rayon::prelude::*;
+
+        return (0..len)
+            .into_par_iter()
+            .map(map)
+            .reduce(|| identity, combine);
+    }
+    #[cfg(not(feature = "parallel"))]
+    let _ = deterministic;
+
+    (0..len).map(map).fold(identity, combine)
+}
@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+
+use crate::float::{Float, Vector};
+
+/// An area of the domain, in cells.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "shape", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Region {
+    Rect { min: Vector, max: Vector },
+    Circle { centre: Vector, radius: Float },
+}
+
+impl Region {
+    pub fn contains(&self, normalized: Vector) -> bool {
+        match *self {
+            Region::Rect { min, max } => normalized.cmpge(min).all() && normalized.cmple(max).all(),
+            Region::Circle { centre, radius } => {
+                normalized.distance_squared(centre) <= radius * radius
+            }
+        }
+    }
+}
+
+/// Drives the flow inside a region every step.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Emitter {
+    pub region: Region,
+    /// Overrides the face velocities inside the region if set.
+    #[serde(default)]
+    pub velocity: Option<Vector>,
+    /// Dye concentration the cells inside the region are raised to.
+    #[serde(default)]
+    pub dye: Float,
+}
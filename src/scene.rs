@@ -0,0 +1,408 @@
+use std::{error, fmt, fs, io, path::Path, str::FromStr};
+
+use glam::{uvec2, UVec2};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    boundary::{Boundaries, Boundary, WallVelocities},
+    error::Error,
+    float::{Float, Vector},
+    particles::{ParticleSettings, Source},
+    region::{Emitter, Region},
+    simulation::{PressureSolver, Simulation, SolverOptions},
+};
+
+/// Everything needed to set up a [`Simulation`], usually read from a TOML
+/// file.
+///
+/// ```toml
+/// [grid]
+/// dimensions = [60, 30]
+/// cell_size = 20.0
+///
+/// [solver]
+/// time_step = 0.5
+/// method = "gauss_seidel"
+/// iterations = 50
+///
+/// [boundary]
+/// left = "open"
+/// right = "open"
+///
+/// [wall_velocity]
+/// top = 5.0
+///
+/// [initial]
+/// velocity = [0.0, 0.0]
+///
+/// [[obstacles]]
+/// shape = "circle"
+/// centre = [20.0, 15.0]
+/// radius = 4.0
+///
+/// [[emitters]]
+/// region = { shape = "rect", min = [0.0, 12.0], max = [2.0, 18.0] }
+/// velocity = [20.0, 0.0]
+/// dye = 1.0
+///
+/// [particles]
+/// lifetime = 100.0
+///
+/// [[particles.emitters]]
+/// source = { shape = "line", start = [1.0, 10.0], end = [1.0, 20.0] }
+/// rate = 4.0
+/// ```
+///
+/// Regions are given in cells, velocities in world units per time.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Scene {
+    pub grid: GridSettings,
+    pub solver: SolverSettings,
+    pub boundary: Boundaries,
+    pub wall_velocity: WallVelocities,
+    pub initial: InitialConditions,
+    pub obstacles: Vec<Region>,
+    pub emitters: Vec<Emitter>,
+    pub particles: ParticleSettings,
+    pub viewer: ViewerSettings,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GridSettings {
+    pub dimensions: UVec2,
+    pub cell_size: Float,
+}
+
+impl Default for GridSettings {
+    fn default() -> Self {
+        Self {
+            dimensions: uvec2(60, 30),
+            cell_size: 20.,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SolverSettings {
+    pub time_step: Float,
+    pub method: PressureSolver,
+    pub iterations: u32,
+    pub viscosity: Float,
+    /// See [`Simulation::deterministic`].
+    pub deterministic: bool,
+}
+
+impl Default for SolverSettings {
+    fn default() -> Self {
+        let SolverOptions { method, iterations } = SolverOptions::default();
+        Self {
+            time_step: 0.5,
+            method,
+            iterations,
+            viscosity: 0.,
+            deterministic: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InitialConditions {
+    /// Uniform velocity everywhere.
+    pub velocity: Vector,
+    pub dye: Vec<DyeRegion>,
+}
+
+impl Default for InitialConditions {
+    fn default() -> Self {
+        Self {
+            velocity: Vector::ONE,
+            dye: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DyeRegion {
+    pub region: Region,
+    pub value: Float,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ViewerSettings {
+    /// Radius of mouse interaction, in cells.
+    pub interaction_radius: Float,
+}
+
+impl Default for ViewerSettings {
+    fn default() -> Self {
+        Self {
+            interaction_radius: 3.,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(io::Error),
+    /// Malformed TOML or a value of the wrong type, with its location.
+    Parse(toml::de::Error),
+    /// A well-formed value that makes no sense, e.g. a negative time step.
+    Invalid {
+        key: String,
+        message: String,
+    },
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::Io(err) => write!(f, "could not read scene: {err}"),
+            SceneError::Parse(err) => write!(f, "could not parse scene: {err}"),
+            SceneError::Invalid { key, message } => write!(f, "invalid `{key}`: {message}"),
+        }
+    }
+}
+
+impl error::Error for SceneError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            SceneError::Io(err) => Some(err),
+            SceneError::Parse(err) => Some(err),
+            SceneError::Invalid { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for SceneError {
+    fn from(err: io::Error) -> Self {
+        SceneError::Io(err)
+    }
+}
+
+impl From<toml::de::Error> for SceneError {
+    fn from(err: toml::de::Error) -> Self {
+        SceneError::Parse(err)
+    }
+}
+
+fn invalid(key: impl Into<String>, message: impl Into<String>) -> SceneError {
+    SceneError::Invalid {
+        key: key.into(),
+        message: message.into(),
+    }
+}
+
+impl FromStr for Scene {
+    type Err = SceneError;
+
+    fn from_str(s: &str) -> Result<Self, SceneError> {
+        let scene: Scene = toml::from_str(s)?;
+        scene.validate()?;
+        Ok(scene)
+    }
+}
+
+impl Scene {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SceneError> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    pub fn validate(&self) -> Result<(), SceneError> {
+        if self.grid.dimensions.min_element() == 0 {
+            return Err(invalid("grid.dimensions", "must be at least one cell"));
+        }
+        if !positive(self.grid.cell_size) {
+            return Err(invalid("grid.cell_size", "must be positive"));
+        }
+        if !positive(self.solver.time_step) {
+            return Err(invalid("solver.time_step", "must be positive"));
+        }
+        if self.solver.iterations == 0 {
+            return Err(invalid("solver.iterations", "must be at least one"));
+        }
+        if !(self.solver.viscosity.is_finite() && self.solver.viscosity >= 0.) {
+            return Err(invalid("solver.viscosity", "must not be negative"));
+        }
+
+        let periodic = |boundary| boundary == Boundary::Periodic;
+        if periodic(self.boundary.left) != periodic(self.boundary.right) {
+            return Err(invalid(
+                "boundary.right",
+                "left and right have to be periodic together",
+            ));
+        }
+        if periodic(self.boundary.bottom) != periodic(self.boundary.top) {
+            return Err(invalid(
+                "boundary.top",
+                "bottom and top have to be periodic together",
+            ));
+        }
+        let WallVelocities {
+            left,
+            right,
+            bottom,
+            top,
+        } = self.wall_velocity;
+        for (side, velocity) in [
+            ("left", left),
+            ("right", right),
+            ("bottom", bottom),
+            ("top", top),
+        ] {
+            if !velocity.is_finite() {
+                return Err(invalid(format!("wall_velocity.{side}"), "must be finite"));
+            }
+        }
+        if !self.initial.velocity.is_finite() {
+            return Err(invalid("initial.velocity", "must be finite"));
+        }
+
+        for (i, dye) in self.initial.dye.iter().enumerate() {
+            validate_region(&dye.region, &format!("initial.dye[{i}].region"))?;
+            if !(dye.value.is_finite() && dye.value >= 0.) {
+                return Err(invalid(
+                    format!("initial.dye[{i}].value"),
+                    "must not be negative",
+                ));
+            }
+        }
+        for (i, obstacle) in self.obstacles.iter().enumerate() {
+            validate_region(obstacle, &format!("obstacles[{i}]"))?;
+        }
+        for (i, emitter) in self.emitters.iter().enumerate() {
+            validate_region(&emitter.region, &format!("emitters[{i}].region"))?;
+            if emitter
+                .velocity
+                .is_some_and(|velocity| !velocity.is_finite())
+            {
+                return Err(invalid(format!("emitters[{i}].velocity"), "must be finite"));
+            }
+            if !(emitter.dye.is_finite() && emitter.dye >= 0.) {
+                return Err(invalid(
+                    format!("emitters[{i}].dye"),
+                    "must not be negative",
+                ));
+            }
+        }
+
+        if !positive(self.particles.lifetime) && self.particles.lifetime != Float::INFINITY {
+            return Err(invalid("particles.lifetime", "must be positive"));
+        }
+        for (i, emitter) in self.particles.emitters.iter().enumerate() {
+            let key = format!("particles.emitters[{i}]");
+            let points = match emitter.source {
+                Source::Point { position } => vec![("position", position)],
+                Source::Line { start, end } => vec![("start", start), ("end", end)],
+                Source::Box { min, max } => vec![("min", min), ("max", max)],
+            };
+            for (name, point) in points {
+                if !point.is_finite() {
+                    return Err(invalid(format!("{key}.source.{name}"), "must be finite"));
+                }
+            }
+            if let Source::Box { min, max } = emitter.source {
+                if min.cmpgt(max).any() {
+                    return Err(invalid(
+                        format!("{key}.source.max"),
+                        "must not be below `min`",
+                    ));
+                }
+            }
+            if !(emitter.rate.is_finite() && emitter.rate >= 0.) {
+                return Err(invalid(format!("{key}.rate"), "must not be negative"));
+            }
+            if emitter
+                .lifetime
+                .is_some_and(|lifetime| !positive(lifetime) && lifetime != Float::INFINITY)
+            {
+                return Err(invalid(format!("{key}.lifetime"), "must be positive"));
+            }
+        }
+
+        if !(self.viewer.interaction_radius.is_finite() && self.viewer.interaction_radius >= 0.) {
+            return Err(invalid("viewer.interaction_radius", "must not be negative"));
+        }
+
+        Ok(())
+    }
+
+    /// Builds the simulation at its initial state, after checking the scene
+    /// with [`Scene::validate`].
+    pub fn simulation(&self) -> Result<Simulation, SceneError> {
+        self.validate()?;
+        let mut simulation = Simulation::new(
+            self.grid.dimensions,
+            self.grid.cell_size,
+            self.solver.time_step,
+        )
+        .map_err(|err| match err {
+            Error::EmptyDimensions(_) => invalid("grid.dimensions", err.to_string()),
+            Error::NotPositive {
+                name: "cell size", ..
+            } => invalid("grid.cell_size", err.to_string()),
+            _ => invalid("solver.time_step", err.to_string()),
+        })?;
+
+        simulation.solver = SolverOptions {
+            method: self.solver.method,
+            iterations: self.solver.iterations,
+        };
+        simulation.boundaries = self.boundary;
+        simulation.wall_velocities = self.wall_velocity;
+        simulation.viscosity = self.solver.viscosity;
+        simulation.deterministic = self.solver.deterministic;
+        simulation.emitters = self.emitters.clone();
+
+        simulation.velocities_x.fill(self.initial.velocity.x);
+        simulation.velocities_y.fill(self.initial.velocity.y);
+
+        for cell in simulation.solids.indices() {
+            let normalized = simulation.solids.position(cell);
+            simulation.solids[cell] = self
+                .obstacles
+                .iter()
+                .any(|obstacle| obstacle.contains(normalized));
+            for dye in &self.initial.dye {
+                if dye.region.contains(normalized) {
+                    simulation.dye[cell] = dye.value;
+                }
+            }
+        }
+
+        Ok(simulation)
+    }
+}
+
+fn validate_region(region: &Region, key: &str) -> Result<(), SceneError> {
+    match *region {
+        Region::Rect { min, max } => {
+            for (name, corner) in [("min", min), ("max", max)] {
+                if !corner.is_finite() {
+                    return Err(invalid(format!("{key}.{name}"), "must be finite"));
+                }
+            }
+            if min.cmpgt(max).any() {
+                return Err(invalid(format!("{key}.max"), "must not be below `min`"));
+            }
+        }
+        Region::Circle { centre, radius } => {
+            if !centre.is_finite() {
+                return Err(invalid(format!("{key}.centre"), "must be finite"));
+            }
+            if !positive(radius) {
+                return Err(invalid(format!("{key}.radius"), "must be positive"));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn positive(value: Float) -> bool {
+    value.is_finite() && value > 0.
+}
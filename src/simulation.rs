@@ -1,7 +1,21 @@
-use std::mem::swap;
-
-use glam::{ivec2, mat2, uvec2, vec2, IVec2, UVec2, Vec2, Vec3};
-
+use std::mem;
+
+use glam::{ivec2, uvec2, vec2, IVec2, UVec2, Vec2, Vec3};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::{
+    boundary::{Boundaries, Boundary, WallVelocities},
+    diagnostics::Diagnostics,
+    error::{check_dimensions, Error},
+    float::{to_f32, to_vec2, Float, Vector},
+    grid::{Axis, CellGrid, FaceGrid},
+    reduce::reduce,
+    region::Emitter,
+    stencil::{Stencil, OFFSETS},
+};
+
+/// A sampled velocity for display, positioned in world units.
 #[derive(Debug, Clone, Copy)]
 pub struct Cell {
     pub position: Vec2,
@@ -9,340 +23,627 @@ pub struct Cell {
     pub color: Vec3,
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PressureSolver {
+    /// Every sweep reads only the pressures of the previous sweep.
+    #[default]
+    Jacobi,
+    /// Every sweep updates the pressures in place, converging faster.
+    GaussSeidel,
+    /// Gauss-Seidel in two half sweeps, first over the cells with even
+    /// `x + y` and then over the odd ones, each of which runs in parallel
+    /// with the `parallel` feature.
+    RedBlack,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SolverOptions {
+    pub method: PressureSolver,
+    /// Number of sweeps of the pressure solve per step.
+    pub iterations: u32,
+}
+
+impl Default for SolverOptions {
+    fn default() -> Self {
+        Self {
+            method: PressureSolver::Jacobi,
+            iterations: 100,
+        }
+    }
+}
+
+/// Incompressible flow in a box of `dimensions` cells, inviscid unless
+/// `viscosity` is set.
+///
+/// Positions passed in and out are in world units, i.e. cells scaled by
+/// `cell_size`, with the origin at the lower left corner of the domain.
+#[derive(Debug, Clone)]
 pub struct Simulation {
-    pub time_step: f32,
-    pub cell_size: f32,
+    pub time_step: Float,
+    pub cell_size: Float,
+    /// Number of cells along each axis.
     pub dimensions: UVec2,
-    pub pressures: Vec<f32>,
-    pub velocities_x: Vec<f32>,
-    pub velocities_y: Vec<f32>,
+    pub solver: SolverOptions,
+    pub boundaries: Boundaries,
+    pub wall_velocities: WallVelocities,
+    /// Kinematic viscosity in world units squared per time, zero for the
+    /// Euler equations.
+    pub viscosity: Float,
+    /// Sums over the fields run in storage order rather than split across
+    /// threads, so that every run gives bitwise the same results. Only makes
+    /// a difference with the `parallel` feature, everything else is computed
+    /// per entry and independent of the number of threads anyway.
+    pub deterministic: bool,
+    /// Cells occupied by obstacles, which no fluid flows through.
+    pub solids: CellGrid<bool>,
+    pub emitters: Vec<Emitter>,
+    pub pressures: CellGrid<Float>,
+    /// Horizontal velocity component on the vertical cell faces.
+    pub velocities_x: FaceGrid<Float>,
+    /// Vertical velocity component on the horizontal cell faces.
+    pub velocities_y: FaceGrid<Float>,
+    /// Passive scalar carried along with the flow.
+    pub dye: CellGrid<Float>,
+    diagnostics: Diagnostics,
+    /// Created by the first step, so that later ones do not allocate.
+    workspace: Option<Workspace>,
 }
 
-impl Simulation {
-    pub fn new(dimensions: UVec2, cell_size: f32, time_step: f32) -> Self {
-        assert!(dimensions.element_product() != 0);
+/// Buffers the steps write into before swapping them with the fields.
+#[derive(Debug, Clone)]
+struct Workspace {
+    cells: CellGrid<Float>,
+    constants: CellGrid<Float>,
+    faces_x: FaceGrid<Float>,
+    faces_x_back: FaceGrid<Float>,
+    faces_y: FaceGrid<Float>,
+    faces_y_back: FaceGrid<Float>,
+    stencil: Stencil,
+}
 
-        let pressures = vec![0.; dimensions.element_product() as usize];
+impl Workspace {
+    fn new(dimensions: UVec2) -> Self {
+        Self {
+            cells: CellGrid::new(dimensions, 0.),
+            constants: CellGrid::new(dimensions, 0.),
+            faces_x: FaceGrid::new(Axis::X, dimensions, 0.),
+            faces_x_back: FaceGrid::new(Axis::X, dimensions, 0.),
+            faces_y: FaceGrid::new(Axis::Y, dimensions, 0.),
+            faces_y_back: FaceGrid::new(Axis::Y, dimensions, 0.),
+            stencil: Stencil::new(dimensions),
+        }
+    }
+}
 
-        let velocities_x_count = ((dimensions.x + 1) * dimensions.y) as usize;
-        let velocities_y_count = (dimensions.x * (dimensions.y + 1)) as usize;
+enum Neighbor {
+    Fluid(UVec2),
+    Solid,
+    Open,
+}
+
+impl Simulation {
+    /// Starts with a uniform velocity of one along both axes, surrounded by
+    /// walls.
+    pub fn new(dimensions: UVec2, cell_size: Float, time_step: Float) -> Result<Self, Error> {
+        check_dimensions(dimensions)?;
+        for (name, value) in [("cell size", cell_size), ("time step", time_step)] {
+            if !(value.is_finite() && value > 0.) {
+                return Err(Error::NotPositive { name, value });
+            }
+        }
 
-        let velocities_x = vec![1.; velocities_x_count];
-        let velocities_y = vec![1.; velocities_y_count];
+        let pressures = CellGrid::new(dimensions, 0.);
 
-        Self {
+        let velocities_x = FaceGrid::new(Axis::X, dimensions, 1.);
+        let velocities_y = FaceGrid::new(Axis::Y, dimensions, 1.);
+
+        Ok(Self {
             time_step,
             dimensions,
             cell_size,
+            solver: SolverOptions::default(),
+            boundaries: Boundaries::default(),
+            wall_velocities: WallVelocities::default(),
+            viscosity: 0.,
+            deterministic: false,
+            solids: CellGrid::new(dimensions, false),
+            emitters: Vec::new(),
             pressures,
             velocities_x,
             velocities_y,
-        }
+            dye: CellGrid::new(dimensions, 0.),
+            diagnostics: Diagnostics::default(),
+            workspace: None,
+        })
     }
 
+    /// Horizontal face velocities, drawn in red.
     pub fn velocities_x(&self) -> impl Iterator<Item = Cell> + '_ {
-        self.velocities_x_iter().map(|cell| {
-            let position = (cell.as_vec2() + Vec2::Y * 0.5) * self.cell_size;
-            let velocity = vec2(self.velocities_x[self.velocities_x_idx(cell)], 0.);
+        self.velocities_x.enumerate().map(|(face, velocity_x)| {
+            let position = self.velocities_x.position(face) * self.cell_size;
+            let velocity = vec2(to_f32(*velocity_x), 0.);
             let color = Vec3::X;
             Cell {
-                position,
+                position: to_vec2(position),
                 velocity,
                 color,
             }
         })
     }
 
+    /// Vertical face velocities, drawn in green.
     pub fn velocities_y(&self) -> impl Iterator<Item = Cell> + '_ {
-        self.velocities_y_iter().map(|cell| {
-            let position = (cell.as_vec2() + Vec2::X * 0.5) * self.cell_size;
-            let velocity = vec2(0., self.velocities_y[self.velocities_y_idx(cell)]);
+        self.velocities_y.enumerate().map(|(face, velocity_y)| {
+            let position = self.velocities_y.position(face) * self.cell_size;
+            let velocity = vec2(0., to_f32(*velocity_y));
             let color = Vec3::Y;
             Cell {
-                position,
+                position: to_vec2(position),
                 velocity,
                 color,
             }
         })
     }
 
+    /// Interpolated velocities at the cell centres, drawn in white.
     pub fn cells(&self) -> impl Iterator<Item = Cell> + '_ {
-        self.cell_iter().map(|cell| {
-            let position = (cell.as_vec2() + Vec2::ONE * 0.5) * self.cell_size;
+        self.pressures.indices().map(|cell| {
+            let position = self.pressures.position(cell) * self.cell_size;
             let velocity = self.interpolate_velocity(position);
-            let color = Vec3::X * self.pressures[self.pressures_idx(cell)] * 0.1;
+            let color = Vec3::ONE;
             Cell {
-                position,
-                velocity,
+                position: to_vec2(position),
+                velocity: to_vec2(velocity),
                 color,
             }
         })
     }
 
-    pub fn interact(&mut self, position: Vec2, velocity: Vec2, radius: f32) {
+    /// Adds `velocity` to all faces within a square of `radius` around
+    /// `position`.
+    pub fn interact(&mut self, position: Vector, velocity: Vector, radius: Float) {
         let steps = (radius / self.cell_size) as i32;
         let normalized = (position / self.cell_size).floor().as_ivec2();
         for i in -steps..=steps {
             for j in -steps..=steps {
                 let cell = normalized + ivec2(i, j);
-                *self.velocity_x_mut(cell) += velocity.x;
-                *self.velocity_y_mut(cell) += velocity.y;
+                *self.velocities_x.clamped_mut(cell) += velocity.x;
+                *self.velocities_y.clamped_mut(cell) += velocity.y;
             }
         }
     }
 
+    /// Applies the emitters, advects the velocities and dye by `time_step`
+    /// and projects the velocities onto a divergence free field.
+    ///
+    /// Afterwards the [`Diagnostics`] of the step are logged at debug level
+    /// and available from [`Simulation::diagnostics`].
     pub fn step(&mut self) {
+        self.emit();
         self.boundary();
         self.advect();
         self.boundary();
-        self.project();
+        if self.viscosity > 0. {
+            self.diffuse();
+        }
+        let pressure_residual = self.project();
+
+        self.diagnostics = Diagnostics::new(self, pressure_residual);
+        let Diagnostics {
+            divergence_rms,
+            divergence_max,
+            kinetic_energy,
+            max_speed,
+            cfl,
+            pressure_residual,
+            pressure_iterations,
+        } = self.diagnostics;
+        debug!(
+            divergence_rms,
+            divergence_max,
+            kinetic_energy,
+            max_speed,
+            cfl,
+            pressure_residual,
+            pressure_iterations,
+            "step"
+        );
     }
 
-    fn pressures_idx(&self, clamped: UVec2) -> usize {
-        assert!(clamped.x < self.dimensions.x);
-        assert!(clamped.y < self.dimensions.y);
-        (clamped.x + clamped.y * self.dimensions.x) as usize
+    /// Diagnostics of the last step, all zero before the first one.
+    pub fn diagnostics(&self) -> Diagnostics {
+        self.diagnostics
     }
 
-    fn velocities_x_idx(&self, clamped: UVec2) -> usize {
-        assert!(clamped.x < self.dimensions.x + 1);
-        assert!(clamped.y < self.dimensions.y);
-        (clamped.x + clamped.y * (self.dimensions.x + 1)) as usize
+    /// Bilinearly interpolated velocity at a position in world units.
+    pub fn interpolate_velocity(&self, position: Vector) -> Vector {
+        self.interpolate_velocity_with_normalized(position / self.cell_size)
     }
 
-    fn velocities_y_idx(&self, clamped: UVec2) -> usize {
-        assert!(clamped.x < self.dimensions.x);
-        assert!(clamped.y < self.dimensions.y + 1);
-        (clamped.x * (self.dimensions.y + 1) + clamped.y) as usize
+    /// Discrete divergence of the velocities per cell, zero in solids.
+    pub fn divergence(&self) -> CellGrid<Float> {
+        CellGrid::from_fn(self.dimensions, |cell| self.divergence_at(cell))
     }
 
-    fn velocities_x_dimensions(&self) -> UVec2 {
-        self.dimensions + UVec2::X
+    pub(crate) fn divergence_at(&self, cell: UVec2) -> Float {
+        self.outflow(cell) / self.cell_size
     }
 
-    fn velocities_y_dimensions(&self) -> UVec2 {
-        self.dimensions + UVec2::Y
+    /// Curl of the velocities per cell, averaged from the cell corners.
+    pub fn vorticity(&self) -> CellGrid<Float> {
+        CellGrid::from_fn(self.dimensions, |cell| {
+            [IVec2::ZERO, IVec2::X, IVec2::Y, IVec2::ONE]
+                .into_iter()
+                .map(|offset| self.corner_vorticity(cell.as_ivec2() + offset))
+                .sum::<Float>()
+                / 4.
+        })
     }
 
-    fn velocity_x_mut(&mut self, normalized: IVec2) -> &mut f32 {
-        let clamped = normalized
-            .max(IVec2::ZERO)
-            .as_uvec2()
-            .min(self.velocities_x_dimensions() - UVec2::ONE);
-        let idx = self.velocities_x_idx(clamped);
-        &mut self.velocities_x[idx]
+    /// Half the squared face velocities, integrated over the cell area.
+    pub fn kinetic_energy(&self) -> Float {
+        let velocities_x = self.velocities_x.as_slice();
+        let velocities_y = self.velocities_y.as_slice();
+        let squared_speeds = reduce(
+            velocities_x.len() + velocities_y.len(),
+            self.deterministic,
+            0.,
+            |index| {
+                let velocity = match index.checked_sub(velocities_x.len()) {
+                    Some(index) => velocities_y[index],
+                    None => velocities_x[index],
+                };
+                velocity * velocity
+            },
+            |a, b| a + b,
+        );
+        0.5 * squared_speeds * self.cell_size * self.cell_size
     }
 
-    fn velocity_x(&self, normalized: IVec2) -> f32 {
-        let clamped = normalized
-            .max(IVec2::ZERO)
-            .as_uvec2()
-            .min(self.velocities_x_dimensions() - UVec2::ONE);
-        self.velocities_x[self.velocities_x_idx(clamped)]
+    /// Half the squared vorticity, integrated over the cell area.
+    pub fn enstrophy(&self) -> Float {
+        let vorticity = self.vorticity();
+        let squared_vorticity = reduce(
+            vorticity.len(),
+            self.deterministic,
+            0.,
+            |index| vorticity.as_slice()[index] * vorticity.as_slice()[index],
+            |a, b| a + b,
+        );
+        0.5 * squared_vorticity * self.cell_size * self.cell_size
     }
 
-    fn velocity_y_mut(&mut self, normalized: IVec2) -> &mut f32 {
-        let clamped = normalized
-            .max(IVec2::ZERO)
-            .as_uvec2()
-            .min(self.velocities_y_dimensions() - UVec2::ONE);
-        let idx = self.velocities_y_idx(clamped);
-        &mut self.velocities_y[idx]
+    /// Hash of the bits of the pressures and velocities, to check that two
+    /// runs are bitwise identical step by step, see
+    /// [`Simulation::deterministic`]. Stable across platforms and versions
+    /// of this crate as long as the fields are.
+    pub fn state_hash(&self) -> u64 {
+        // 64 bit FNV-1a.
+        let mut hash = 0xcbf29ce484222325;
+        for value in self
+            .pressures
+            .iter()
+            .chain(self.velocities_x.iter())
+            .chain(self.velocities_y.iter())
+        {
+            for byte in value.to_le_bytes() {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x100000001b3);
+            }
+        }
+        hash
     }
 
-    fn velocity_y(&self, normalized: IVec2) -> f32 {
-        let clamped = normalized
-            .max(IVec2::ZERO)
-            .as_uvec2()
-            .min(self.velocities_y_dimensions() - UVec2::ONE);
-        self.velocities_y[self.velocities_y_idx(clamped)]
+    /// Velocity leaving a cell through its faces, zero in solids.
+    fn outflow(&self, cell: UVec2) -> Float {
+        if self.solids[cell] {
+            return 0.;
+        }
+        self.velocities_x[cell + UVec2::X] - self.velocities_x[cell]
+            + self.velocities_y[cell + UVec2::Y]
+            - self.velocities_y[cell]
     }
 
-    fn interpolate_velocity_x(&self, normalized: Vec2) -> f32 {
-        let shifted = normalized - 0.5 * Vec2::Y;
-        let reference = shifted.floor().as_ivec2();
-
-        let Vec2 { x: dx, y: dy } = normalized - reference.as_vec2() - 0.5 * Vec2::Y;
-
-        assert!(dx >= 0.);
-        assert!(dx <= 1.);
-        assert!(dy >= 0.);
-        assert!(dy <= 1.);
-
-        vec2(1. - dx, dx).dot(
-            mat2(
-                vec2(
-                    self.velocity_x(reference),
-                    self.velocity_x(reference + IVec2::X),
-                ),
-                vec2(
-                    self.velocity_x(reference + IVec2::Y),
-                    self.velocity_x(reference + IVec2::ONE),
-                ),
-            ) * vec2(1. - dy, dy),
-        )
+    /// Faces beyond the border are clamped, i.e. walls are free slip.
+    fn corner_vorticity(&self, corner: IVec2) -> Float {
+        let wrap = self.boundaries.wrap();
+        let velocity_x = |face| *self.velocities_x.wrapped_or_clamped(face, wrap);
+        let velocity_y = |face| *self.velocities_y.wrapped_or_clamped(face, wrap);
+        (velocity_y(corner) - velocity_y(corner - IVec2::X) - velocity_x(corner)
+            + velocity_x(corner - IVec2::Y))
+            / self.cell_size
     }
 
-    fn interpolate_velocity_y(&self, normalized: Vec2) -> f32 {
-        let shifted = normalized - 0.5 * Vec2::X;
-        let reference = shifted.floor().as_ivec2();
-
-        let Vec2 { x: dx, y: dy } = normalized - reference.as_vec2() - 0.5 * Vec2::X;
-
-        assert!(dx >= 0.);
-        assert!(dx <= 1.);
-        assert!(dy >= 0.);
-        assert!(dy <= 1.);
-
-        vec2(1. - dx, dx).dot(
-            mat2(
-                vec2(
-                    self.velocity_y(reference),
-                    self.velocity_y(reference + IVec2::X),
-                ),
-                vec2(
-                    self.velocity_y(reference + IVec2::Y),
-                    self.velocity_y(reference + IVec2::ONE),
-                ),
-            ) * vec2(1. - dy, dy),
+    fn interpolate_velocity_with_normalized(&self, normalized: Vector) -> Vector {
+        let wrap = self.boundaries.wrap();
+        Vector::new(
+            self.velocities_x.sample_with(normalized, wrap),
+            self.velocities_y.sample_with(normalized, wrap),
         )
     }
 
-    pub fn interpolate_velocity(&self, position: Vec2) -> Vec2 {
-        let normalized = position / self.cell_size;
-        vec2(
-            self.interpolate_velocity_x(normalized),
-            self.interpolate_velocity_y(normalized),
-        )
+    /// The workspace of the previous step, or a new one on the first step or
+    /// after `dimensions` changed.
+    fn take_workspace(&mut self) -> Workspace {
+        self.workspace
+            .take()
+            .filter(|workspace| workspace.cells.dimensions() == self.dimensions)
+            .unwrap_or_else(|| Workspace::new(self.dimensions))
     }
 
-    fn interpolate_velocity_with_normalized(&self, normalized: Vec2) -> Vec2 {
-        vec2(
-            self.interpolate_velocity_x(normalized),
-            self.interpolate_velocity_y(normalized),
-        )
+    fn emit(&mut self) {
+        for emitter in &self.emitters {
+            if let Some(velocity) = emitter.velocity {
+                for face in self.velocities_x.indices() {
+                    if emitter.region.contains(self.velocities_x.position(face)) {
+                        self.velocities_x[face] = velocity.x;
+                    }
+                }
+                for face in self.velocities_y.indices() {
+                    if emitter.region.contains(self.velocities_y.position(face)) {
+                        self.velocities_y[face] = velocity.y;
+                    }
+                }
+            }
+            for cell in self.dye.indices() {
+                if emitter.region.contains(self.dye.position(cell)) {
+                    self.dye[cell] = self.dye[cell].max(emitter.dye);
+                }
+            }
+        }
     }
 
-    fn advect(&mut self) {
-        let velocities_x = self
-            .velocities_x_iter()
-            .map(|UVec2 { x, y }| {
-                let normalized = vec2(x as f32, 0.5 + y as f32);
-                let velocity = self.interpolate_velocity_with_normalized(normalized);
-                let lookup = normalized - self.time_step * velocity / self.cell_size;
-                self.interpolate_velocity_with_normalized(lookup).x
-            })
-            .collect();
-        let velocities_y = self
-            .velocities_y_iter()
-            .map(|UVec2 { x, y }| {
-                let normalized = vec2(0.5 + x as f32, y as f32);
-                let velocity = self.interpolate_velocity_with_normalized(normalized);
-                let lookup = normalized - self.time_step * velocity / self.cell_size;
-                self.interpolate_velocity_with_normalized(lookup).y
-            })
-            .collect();
-
-        self.velocities_x = velocities_x;
-        self.velocities_y = velocities_y;
+    /// Carries the velocities and dye along the flow for one time step, by
+    /// sampling them where the flow comes from. Part of [`Simulation::step`].
+    pub fn advect(&mut self) {
+        let mut workspace = self.take_workspace();
+        let wrap = self.boundaries.wrap();
+        let lookup = |normalized: Vector| {
+            let velocity = self.interpolate_velocity_with_normalized(normalized);
+            normalized - self.time_step * velocity / self.cell_size
+        };
+
+        workspace.faces_x.fill_with(|face| {
+            let normalized = self.velocities_x.position(face);
+            self.velocities_x.sample_with(lookup(normalized), wrap)
+        });
+        workspace.faces_y.fill_with(|face| {
+            let normalized = self.velocities_y.position(face);
+            self.velocities_y.sample_with(lookup(normalized), wrap)
+        });
+        workspace.cells.fill_with(|cell| {
+            let normalized = self.dye.position(cell);
+            self.dye.sample_with(lookup(normalized), wrap)
+        });
+
+        mem::swap(&mut self.velocities_x, &mut workspace.faces_x);
+        mem::swap(&mut self.velocities_y, &mut workspace.faces_y);
+        mem::swap(&mut self.dye, &mut workspace.cells);
+        self.workspace = Some(workspace);
     }
 
-    fn is_fluid(&self, normalized: IVec2) -> bool {
-        normalized.x >= 0
-            && normalized.y >= 0
-            && normalized.x < self.dimensions.x as i32
-            && normalized.y < self.dimensions.y as i32
-
-        // TODO: inner solid cells
+    /// Implicit viscous diffusion of the velocities, where walls and solids
+    /// are no-slip, solved with Jacobi sweeps.
+    fn diffuse(&mut self) {
+        let mut workspace = self.take_workspace();
+        let diffusion = self.viscosity * self.time_step / (self.cell_size * self.cell_size);
+        let diffuse = |faces: &FaceGrid<Float>,
+                       diffused: &mut FaceGrid<Float>,
+                       back: &mut FaceGrid<Float>| {
+            diffused.copy_from(faces);
+            for _ in 0..self.solver.iterations {
+                back.fill_with(|face| self.diffuse_face(faces, diffused, face, diffusion));
+                mem::swap(diffused, back);
+            }
+        };
+        diffuse(
+            &self.velocities_x,
+            &mut workspace.faces_x,
+            &mut workspace.faces_x_back,
+        );
+        diffuse(
+            &self.velocities_y,
+            &mut workspace.faces_y,
+            &mut workspace.faces_y_back,
+        );
+
+        mem::swap(&mut self.velocities_x, &mut workspace.faces_x);
+        mem::swap(&mut self.velocities_y, &mut workspace.faces_y);
+        self.workspace = Some(workspace);
     }
 
-    fn boundary(&mut self) {
-        for i in 0..self.dimensions.x {
-            let bot = self.velocities_y_idx(uvec2(i, 0));
-            let top = self.velocities_y_idx(uvec2(i, self.dimensions.y));
-            self.velocities_y[top] = 0.;
-            self.velocities_y[bot] = 0.;
+    /// One Jacobi update of a face from the velocities before diffusion and
+    /// the current iterate. Faces next to anything but fluid keep their
+    /// velocity.
+    fn diffuse_face(
+        &self,
+        before: &FaceGrid<Float>,
+        faces: &FaceGrid<Float>,
+        face: UVec2,
+        diffusion: Float,
+    ) -> Float {
+        let along = faces.axis().unit().as_ivec2();
+        let across = along.perp();
+        let normalized = face.as_ivec2();
+        if !self.is_fluid(normalized - along) || !self.is_fluid(normalized) {
+            return before[face];
         }
 
-        for j in 0..self.dimensions.y {
-            let left = self.velocities_x_idx(uvec2(0, j));
-            let right = self.velocities_x_idx(uvec2(self.dimensions.x, j));
-            self.velocities_x[left] = 0.;
-            self.velocities_x[right] = 0.;
+        let wrap = self.boundaries.wrap();
+        let velocity = faces[face];
+        let mut sum = 0.;
+        for offset in [along, -along] {
+            sum += *faces.wrapped_or_clamped(normalized + offset, wrap);
         }
+        for offset in [across, -across] {
+            let neighbor = normalized + offset;
+            sum += match (self.neighbor(neighbor - along), self.neighbor(neighbor)) {
+                (Neighbor::Fluid(_), Neighbor::Fluid(_)) => {
+                    *faces.wrapped_or_clamped(neighbor, wrap)
+                }
+                // Mirrored so that the velocity is zero at the solid or the
+                // wall velocity at the wall, halfway between the faces.
+                (Neighbor::Solid, _) | (_, Neighbor::Solid) => {
+                    let wrapped = self.wrap(neighbor);
+                    if self.solids.contains(wrapped) {
+                        -velocity
+                    } else {
+                        2. * self.wall_velocities.beyond(wrapped, self.dimensions) - velocity
+                    }
+                }
+                _ => velocity,
+            };
+        }
+        (before[face] + diffusion * sum) / (1. + 4. * diffusion)
+    }
 
-        // TODO: inner solid cells
+    /// Wraps a cell index along the periodic axes.
+    fn wrap(&self, normalized: IVec2) -> IVec2 {
+        IVec2::select(
+            self.boundaries.wrap(),
+            normalized.rem_euclid(self.dimensions.as_ivec2()),
+            normalized,
+        )
     }
 
-    fn cell_iter(&self) -> impl Iterator<Item = UVec2> + '_ {
-        (0..self.dimensions.y).flat_map(|j| (0..self.dimensions.x).map(move |i| uvec2(i, j)))
+    fn neighbor(&self, normalized: IVec2) -> Neighbor {
+        let wrapped = self.wrap(normalized);
+        if self.solids.contains(wrapped) {
+            let cell = wrapped.as_uvec2();
+            if self.solids[cell] {
+                Neighbor::Solid
+            } else {
+                Neighbor::Fluid(cell)
+            }
+        } else {
+            match self.boundaries.beyond(wrapped, self.dimensions) {
+                // Only reached for a periodic side without its pair.
+                Boundary::Wall | Boundary::Periodic => Neighbor::Solid,
+                Boundary::Open => Neighbor::Open,
+            }
+        }
     }
 
-    fn velocities_x_iter(&self) -> impl Iterator<Item = UVec2> + '_ {
-        (0..self.dimensions.y).flat_map(|j| (0..=self.dimensions.x).map(move |i| uvec2(i, j)))
+    fn is_fluid(&self, normalized: IVec2) -> bool {
+        matches!(self.neighbor(normalized), Neighbor::Fluid(_))
     }
 
-    fn velocities_y_iter(&self) -> impl Iterator<Item = UVec2> + '_ {
-        (0..self.dimensions.x).flat_map(|i| (0..=self.dimensions.y).map(move |j| uvec2(i, j)))
+    fn is_solid(&self, normalized: IVec2) -> bool {
+        matches!(self.neighbor(normalized), Neighbor::Solid)
     }
 
-    fn project(&mut self) {
-        let constants: Vec<f32> = self
-            .cell_iter()
-            .map(|cell| {
-                self.cell_size / self.time_step
-                    * (self.velocities_x[self.velocities_x_idx(cell + UVec2::X)]
-                        - self.velocities_x[self.velocities_x_idx(cell)]
-                        + self.velocities_y[self.velocities_y_idx(cell + UVec2::Y)]
-                        - self.velocities_y[self.velocities_y_idx(cell)])
-            })
-            .collect();
+    /// Pressure in a cell next to a face, `None` if no fluid can be there.
+    fn face_pressure(&self, normalized: IVec2) -> Option<Float> {
+        match self.neighbor(normalized) {
+            Neighbor::Fluid(cell) => Some(self.pressures[cell]),
+            Neighbor::Solid => None,
+            Neighbor::Open => Some(0.),
+        }
+    }
 
-        for _ in 0..100 {
-            self.pressures = self
-                .cell_iter()
-                .map(|cell| {
-                    let neigbors = [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y]
-                        .into_iter()
-                        .filter_map(|offset| {
-                            let neighbor = cell.as_ivec2() + offset;
-                            self.is_fluid(neighbor).then_some(neighbor.as_uvec2())
-                        });
+    /// Stops the flow through faces next to solids and walls. Faces on open
+    /// sides keep their velocity, so fluid can enter and leave there.
+    fn boundary(&mut self) {
+        for face in self.velocities_x.indices() {
+            let right = face.as_ivec2();
+            if self.is_solid(right - IVec2::X) || self.is_solid(right) {
+                self.velocities_x[face] = 0.;
+            }
+        }
 
-                    (neigbors
-                        .clone()
-                        .map(|neighbor| self.pressures[self.pressures_idx(neighbor)])
-                        .sum::<f32>()
-                        - constants[self.pressures_idx(cell)])
-                        / neigbors.count() as f32
-                })
-                .collect()
+        for face in self.velocities_y.indices() {
+            let top = face.as_ivec2();
+            if self.is_solid(top - IVec2::Y) || self.is_solid(top) {
+                self.velocities_y[face] = 0.;
+            }
         }
 
-        let mut velocities_x = Default::default();
-        swap(&mut velocities_x, &mut self.velocities_x);
-        for (cell, velocity_x) in self.velocities_x_iter().zip(velocities_x.iter_mut()) {
-            if cell.x == 0 || cell.x == self.dimensions.x {
-                continue;
+        // The last face along a periodic axis is the first one again.
+        let wrap = self.boundaries.wrap();
+        if wrap.x {
+            for j in 0..self.dimensions.y {
+                self.velocities_x[uvec2(self.dimensions.x, j)] = self.velocities_x[uvec2(0, j)];
+            }
+        }
+        if wrap.y {
+            for i in 0..self.dimensions.x {
+                self.velocities_y[uvec2(i, self.dimensions.y)] = self.velocities_y[uvec2(i, 0)];
             }
-            let pressure_gradient = (self.pressures[self.pressures_idx(cell)]
-                - self.pressures[self.pressures_idx(cell - UVec2::X)])
-                / self.cell_size;
-            *velocity_x -= self.time_step * pressure_gradient;
         }
-        self.velocities_x = velocities_x;
+    }
 
-        let mut velocities_y = Default::default();
-        swap(&mut velocities_y, &mut self.velocities_y);
-        for (cell, velocity_y) in self.velocities_y_iter().zip(velocities_y.iter_mut()) {
-            if cell.y == 0 || cell.y == self.dimensions.y {
-                continue;
+    /// Which neighbors of a cell are fluid and how many fluid can flow to or
+    /// from, where open sides have zero pressure. Solids have none.
+    fn pressure_neighbors(&self, cell: UVec2) -> ([bool; 4], u32) {
+        let mut fluid = [false; 4];
+        let mut count = 0;
+        if self.solids[cell] {
+            return (fluid, count);
+        }
+        for (fluid, offset) in fluid.iter_mut().zip(OFFSETS) {
+            match self.neighbor(cell.as_ivec2() + offset) {
+                Neighbor::Fluid(_) => {
+                    *fluid = true;
+                    count += 1;
+                }
+                Neighbor::Open => count += 1,
+                Neighbor::Solid => {}
             }
-            let pressure_gradient = (self.pressures[self.pressures_idx(cell)]
-                - self.pressures[self.pressures_idx(cell - UVec2::Y)])
-                / self.cell_size;
-            *velocity_y -= self.time_step * pressure_gradient;
         }
-        self.velocities_y = velocities_y;
+        (fluid, count)
+    }
+
+    /// Solves for the pressures that make the velocities divergence free and
+    /// subtracts their gradient. Part of [`Simulation::step`], returns the
+    /// root mean square residual of the pressure solve.
+    pub fn project(&mut self) -> Float {
+        let mut workspace = self.take_workspace();
+        let constants = &mut workspace.constants;
+        constants.fill_with(|cell| self.cell_size / self.time_step * self.outflow(cell));
+        let constants = &workspace.constants;
+        let stencil = &mut workspace.stencil;
+        stencil.update(self.boundaries.wrap(), &self.pressures, |cell| {
+            self.pressure_neighbors(cell)
+        });
+
+        let iterations = self.solver.iterations;
+        match self.solver.method {
+            PressureSolver::Jacobi => stencil.jacobi(constants, iterations),
+            PressureSolver::GaussSeidel => stencil.gauss_seidel(constants, iterations),
+            PressureSolver::RedBlack => stencil.red_black(constants, iterations),
+        }
+        stencil.store(&mut self.pressures);
+        let pressure_residual = stencil.residual(constants, &self.solids, self.deterministic);
+
+        workspace.faces_x.fill_with(|face| {
+            let right = face.as_ivec2();
+            let velocity = self.velocities_x[face];
+            let (Some(pressure_left), Some(pressure_right)) = (
+                self.face_pressure(right - IVec2::X),
+                self.face_pressure(right),
+            ) else {
+                return velocity;
+            };
+            let pressure_gradient = (pressure_right - pressure_left) / self.cell_size;
+            velocity - self.time_step * pressure_gradient
+        });
+        workspace.faces_y.fill_with(|face| {
+            let top = face.as_ivec2();
+            let velocity = self.velocities_y[face];
+            let (Some(pressure_bottom), Some(pressure_top)) =
+                (self.face_pressure(top - IVec2::Y), self.face_pressure(top))
+            else {
+                return velocity;
+            };
+            let pressure_gradient = (pressure_top - pressure_bottom) / self.cell_size;
+            velocity - self.time_step * pressure_gradient
+        });
+
+        mem::swap(&mut self.velocities_x, &mut workspace.faces_x);
+        mem::swap(&mut self.velocities_y, &mut workspace.faces_y);
+        self.workspace = Some(workspace);
+
+        pressure_residual
     }
 }
@@ -0,0 +1,314 @@
+use std::{
+    error, fmt,
+    io::{self, Read, Write},
+    mem,
+};
+
+use glam::uvec2;
+
+use crate::{
+    boundary::{Boundaries, Boundary, WallVelocities},
+    float::{Float, Vector},
+    grid::Grid,
+    region::{Emitter, Region},
+    simulation::{PressureSolver, Simulation, SolverOptions},
+};
+
+const MAGIC: [u8; 4] = *b"MACG";
+
+/// Bumped whenever the layout changes.
+const VERSION: u32 = 1;
+
+/// Most cells a snapshot may declare, checked before anything is allocated
+/// so that a corrupt header cannot exhaust the memory.
+const MAX_CELLS: u64 = 1 << 24;
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    /// Not a snapshot at all.
+    Magic([u8; 4]),
+    /// Written by another version of this crate.
+    Version(u32),
+    /// A value that no version of the format writes.
+    Invalid(&'static str),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Io(err) => write!(f, "could not read snapshot: {err}"),
+            SnapshotError::Magic(magic) => write!(f, "not a snapshot, starts with {magic:?}"),
+            SnapshotError::Version(version) => {
+                write!(f, "snapshot version {version} is not {VERSION}")
+            }
+            SnapshotError::Invalid(what) => write!(f, "invalid {what} in snapshot"),
+        }
+    }
+}
+
+impl error::Error for SnapshotError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            SnapshotError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SnapshotError {
+    fn from(err: io::Error) -> Self {
+        SnapshotError::Io(err)
+    }
+}
+
+impl Simulation {
+    /// Writes the complete state in a versioned little endian format, which
+    /// [`Simulation::load`] restores exactly.
+    pub fn save(&self, mut writer: impl Write) -> io::Result<()> {
+        let w = &mut writer;
+        w.write_all(&MAGIC)?;
+        write_u32(w, VERSION)?;
+        write_u8(w, mem::size_of::<Float>() as u8)?;
+
+        write_u32(w, self.dimensions.x)?;
+        write_u32(w, self.dimensions.y)?;
+        write_float(w, self.cell_size)?;
+        write_float(w, self.time_step)?;
+
+        let method = match self.solver.method {
+            PressureSolver::Jacobi => 0,
+            PressureSolver::GaussSeidel => 1,
+            PressureSolver::RedBlack => 2,
+        };
+        write_u8(w, method)?;
+        write_u32(w, self.solver.iterations)?;
+
+        let Boundaries {
+            left,
+            right,
+            bottom,
+            top,
+        } = self.boundaries;
+        for boundary in [left, right, bottom, top] {
+            let boundary = match boundary {
+                Boundary::Wall => 0,
+                Boundary::Open => 1,
+                Boundary::Periodic => 2,
+            };
+            write_u8(w, boundary)?;
+        }
+
+        let WallVelocities {
+            left,
+            right,
+            bottom,
+            top,
+        } = self.wall_velocities;
+        write_floats(w, &[left, right, bottom, top, self.viscosity])?;
+        write_u8(w, self.deterministic as u8)?;
+
+        write_u32(w, self.emitters.len() as u32)?;
+        for emitter in &self.emitters {
+            let (shape, a, b) = match emitter.region {
+                Region::Rect { min, max } => (0, min, max),
+                Region::Circle { centre, radius } => (1, centre, Vector::splat(radius)),
+            };
+            write_u8(w, shape)?;
+            write_floats(w, &[a.x, a.y, b.x, b.y])?;
+            let velocity = emitter.velocity.unwrap_or(Vector::ZERO);
+            write_u8(w, emitter.velocity.is_some() as u8)?;
+            write_floats(w, &[velocity.x, velocity.y, emitter.dye])?;
+        }
+
+        let solids = self
+            .solids
+            .iter()
+            .map(|&solid| solid as u8)
+            .collect::<Vec<_>>();
+        w.write_all(&solids)?;
+
+        write_floats(w, self.pressures.as_slice())?;
+        write_floats(w, self.velocities_x.as_slice())?;
+        write_floats(w, self.velocities_y.as_slice())?;
+        write_floats(w, self.dye.as_slice())?;
+
+        writer.flush()
+    }
+
+    pub fn load(mut reader: impl Read) -> Result<Self, SnapshotError> {
+        let r = &mut reader;
+
+        let mut magic = [0; 4];
+        r.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(SnapshotError::Magic(magic));
+        }
+        let version = read_u32(r)?;
+        if version != VERSION {
+            return Err(SnapshotError::Version(version));
+        }
+        // Snapshots of the other precision are converted.
+        let width = read_u8(r)?;
+        if width != 4 && width != 8 {
+            return Err(SnapshotError::Invalid("float width"));
+        }
+
+        let dimensions = uvec2(read_u32(r)?, read_u32(r)?);
+        if dimensions.min_element() == 0 || dimensions.x as u64 * dimensions.y as u64 > MAX_CELLS {
+            return Err(SnapshotError::Invalid("dimensions"));
+        }
+        let cell_size = read_float(r, width)?;
+        let time_step = read_float(r, width)?;
+        let mut simulation = Simulation::new(dimensions, cell_size, time_step)
+            .map_err(|_| SnapshotError::Invalid("cell size or time step"))?;
+
+        simulation.solver = SolverOptions {
+            method: match read_u8(r)? {
+                0 => PressureSolver::Jacobi,
+                1 => PressureSolver::GaussSeidel,
+                2 => PressureSolver::RedBlack,
+                _ => return Err(SnapshotError::Invalid("pressure solver")),
+            },
+            iterations: read_u32(r)?,
+        };
+        if simulation.solver.iterations == 0 {
+            return Err(SnapshotError::Invalid("solver iterations"));
+        }
+
+        let mut boundaries = [Boundary::Wall; 4];
+        for boundary in &mut boundaries {
+            *boundary = match read_u8(r)? {
+                0 => Boundary::Wall,
+                1 => Boundary::Open,
+                2 => Boundary::Periodic,
+                _ => return Err(SnapshotError::Invalid("boundary")),
+            };
+        }
+        let [left, right, bottom, top] = boundaries;
+        let periodic = |boundary| boundary == Boundary::Periodic;
+        if periodic(left) != periodic(right) || periodic(bottom) != periodic(top) {
+            return Err(SnapshotError::Invalid("boundary"));
+        }
+        simulation.boundaries = Boundaries {
+            left,
+            right,
+            bottom,
+            top,
+        };
+
+        let [left, right, bottom, top, viscosity] = read_array(r, width)?;
+        if ![left, right, bottom, top]
+            .iter()
+            .all(|velocity| velocity.is_finite())
+        {
+            return Err(SnapshotError::Invalid("wall velocity"));
+        }
+        if !(viscosity.is_finite() && viscosity >= 0.) {
+            return Err(SnapshotError::Invalid("viscosity"));
+        }
+        simulation.wall_velocities = WallVelocities {
+            left,
+            right,
+            bottom,
+            top,
+        };
+        simulation.viscosity = viscosity;
+        simulation.deterministic = read_u8(r)? != 0;
+
+        let emitter_count = read_u32(r)?;
+        for _ in 0..emitter_count {
+            let shape = read_u8(r)?;
+            let [ax, ay, bx, by] = read_array(r, width)?;
+            let region = match shape {
+                0 => Region::Rect {
+                    min: Vector::new(ax, ay),
+                    max: Vector::new(bx, by),
+                },
+                1 => Region::Circle {
+                    centre: Vector::new(ax, ay),
+                    radius: bx,
+                },
+                _ => return Err(SnapshotError::Invalid("emitter shape")),
+            };
+            let has_velocity = read_u8(r)? != 0;
+            let [velocity_x, velocity_y, dye] = read_array(r, width)?;
+            simulation.emitters.push(Emitter {
+                region,
+                velocity: has_velocity.then_some(Vector::new(velocity_x, velocity_y)),
+                dye,
+            });
+        }
+
+        let mut solids = vec![0; simulation.solids.len()];
+        r.read_exact(&mut solids)?;
+        for (solid, byte) in simulation.solids.iter_mut().zip(solids) {
+            *solid = byte != 0;
+        }
+
+        read_grid(r, width, &mut simulation.pressures)?;
+        read_grid(r, width, &mut simulation.velocities_x)?;
+        read_grid(r, width, &mut simulation.velocities_y)?;
+        read_grid(r, width, &mut simulation.dye)?;
+
+        Ok(simulation)
+    }
+}
+
+fn write_u8(writer: &mut impl Write, value: u8) -> io::Result<()> {
+    writer.write_all(&[value])
+}
+
+fn write_u32(writer: &mut impl Write, value: u32) -> io::Result<()> {
+    writer.write_all(&value.to_le_bytes())
+}
+
+fn write_float(writer: &mut impl Write, value: Float) -> io::Result<()> {
+    writer.write_all(&value.to_le_bytes())
+}
+
+fn write_floats(writer: &mut impl Write, values: &[Float]) -> io::Result<()> {
+    values
+        .iter()
+        .try_for_each(|&value| write_float(writer, value))
+}
+
+fn read_u8(reader: &mut impl Read) -> io::Result<u8> {
+    let mut bytes = [0; 1];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes[0])
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+/// Reads an `f32` or `f64`, by its `width` in bytes.
+fn read_float(reader: &mut impl Read, width: u8) -> io::Result<Float> {
+    if width == 4 {
+        let mut bytes = [0; 4];
+        reader.read_exact(&mut bytes)?;
+        Ok(f32::from_le_bytes(bytes) as Float)
+    } else {
+        let mut bytes = [0; 8];
+        reader.read_exact(&mut bytes)?;
+        Ok(f64::from_le_bytes(bytes) as Float)
+    }
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read, width: u8) -> io::Result<[Float; N]> {
+    let mut values = [0.; N];
+    for value in &mut values {
+        *value = read_float(reader, width)?;
+    }
+    Ok(values)
+}
+
+fn read_grid(reader: &mut impl Read, width: u8, grid: &mut Grid<Float>) -> io::Result<()> {
+    for value in grid.iter_mut() {
+        *value = read_float(reader, width)?;
+    }
+    Ok(())
+}
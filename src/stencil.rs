@@ -0,0 +1,292 @@
+//! Row-oriented kernels of the pressure solve.
+//!
+//! The pressures are kept with a ghost cell on every side, so that the
+//! neighbors of a row of cells are contiguous slices of the rows around it.
+//! Whether a neighbor takes part is a factor of one or zero rather than a
+//! branch, which lets the compiler vectorise the sweeps.
+
+use std::mem;
+
+use glam::{BVec2, IVec2, UVec2};
+
+use crate::{float::Float, grid::CellGrid, reduce::reduce};
+
+/// Directions of the neighbors, in the order they are summed.
+pub(crate) const OFFSETS: [IVec2; 4] = [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y];
+
+/// Coefficients of the pressure equation and the padded pressures it is
+/// solved on, reused from step to step.
+#[derive(Debug, Clone)]
+pub(crate) struct Stencil {
+    coefficients: Coefficients,
+    pressures: Vec<Float>,
+    back: Vec<Float>,
+}
+
+#[derive(Debug, Clone)]
+struct Coefficients {
+    dimensions: UVec2,
+    wrap: BVec2,
+    /// One where the neighbor towards each of [`OFFSETS`] is fluid, zero
+    /// otherwise.
+    fluid: [Vec<Float>; 4],
+    /// Number of neighbors fluid can flow to or from, zero in solids.
+    counts: Vec<Float>,
+}
+
+impl Stencil {
+    pub(crate) fn new(dimensions: UVec2) -> Self {
+        let cells = dimensions.element_product() as usize;
+        let padded = ((dimensions.x + 2) * (dimensions.y + 2)) as usize;
+        Self {
+            coefficients: Coefficients {
+                dimensions,
+                wrap: BVec2::FALSE,
+                fluid: [(); 4].map(|_| vec![0.; cells]),
+                counts: vec![0.; cells],
+            },
+            pressures: vec![0.; padded],
+            back: vec![0.; padded],
+        }
+    }
+
+    /// Sets up the coefficients from which of the [`OFFSETS`] of each cell
+    /// are fluid and how many neighbors it has, and loads the pressures.
+    pub(crate) fn update(
+        &mut self,
+        wrap: BVec2,
+        pressures: &CellGrid<Float>,
+        neighbors: impl Fn(UVec2) -> ([bool; 4], u32),
+    ) {
+        let coefficients = &mut self.coefficients;
+        coefficients.wrap = wrap;
+        for (index, cell) in pressures.indices().enumerate() {
+            let (fluid, count) = neighbors(cell);
+            for (factors, fluid) in coefficients.fluid.iter_mut().zip(fluid) {
+                factors[index] = if fluid { 1. } else { 0. };
+            }
+            coefficients.counts[index] = count as Float;
+        }
+
+        let width = coefficients.dimensions.x as usize;
+        for (padded, row) in self
+            .pressures
+            .chunks_mut(width + 2)
+            .skip(1)
+            .zip(pressures.as_slice().chunks(width))
+        {
+            padded[1..=width].copy_from_slice(row);
+        }
+        coefficients.refresh(&mut self.pressures);
+    }
+
+    pub(crate) fn store(&self, pressures: &mut CellGrid<Float>) {
+        let width = self.coefficients.dimensions.x as usize;
+        for (row, padded) in pressures
+            .as_mut_slice()
+            .chunks_mut(width)
+            .zip(self.pressures.chunks(width + 2).skip(1))
+        {
+            row.copy_from_slice(&padded[1..=width]);
+        }
+    }
+
+    /// Sweeps that read only the pressures of the previous sweep.
+    pub(crate) fn jacobi(&mut self, constants: &CellGrid<Float>, iterations: u32) {
+        for _ in 0..iterations {
+            self.sweep(constants, None);
+        }
+    }
+
+    /// Half sweeps over the cells with even and then odd `x + y`.
+    pub(crate) fn red_black(&mut self, constants: &CellGrid<Float>, iterations: u32) {
+        for _ in 0..iterations {
+            for parity in [0, 1] {
+                self.sweep(constants, Some(parity));
+            }
+        }
+    }
+
+    /// Sweeps updating the pressures in place, which runs cell by cell.
+    pub(crate) fn gauss_seidel(&mut self, constants: &CellGrid<Float>, iterations: u32) {
+        let coefficients = &self.coefficients;
+        let pressures = &mut self.pressures;
+        let [width, height] = coefficients.dimensions.to_array().map(|n| n as usize);
+        let padded = width + 2;
+        let constants = constants.as_slice();
+
+        for _ in 0..iterations {
+            for j in 0..height {
+                for i in 0..width {
+                    let cell = i + j * width;
+                    let centre = i + 1 + (j + 1) * padded;
+                    let sum = coefficients.neighbor_sum(pressures, cell, centre);
+                    pressures[centre] = relax(sum, constants[cell], coefficients.counts[cell]);
+                    // The cell is the right neighbor of the last one in the
+                    // row across the periodic border.
+                    if i == 0 && coefficients.wrap.x {
+                        pressures[centre + width] = pressures[centre];
+                    }
+                }
+                if j == 0 && coefficients.wrap.y {
+                    pressures.copy_within(padded..2 * padded, (height + 1) * padded);
+                }
+            }
+            coefficients.refresh(pressures);
+        }
+    }
+
+    /// Root mean square of how far the pressures are off from solving the
+    /// pressure equation in the cells that are not `solids`.
+    pub(crate) fn residual(
+        &self,
+        constants: &CellGrid<Float>,
+        solids: &CellGrid<bool>,
+        deterministic: bool,
+    ) -> Float {
+        let coefficients = &self.coefficients;
+        let width = coefficients.dimensions.x as usize;
+        let (sum, count) = reduce(
+            solids.len(),
+            deterministic,
+            (0., 0),
+            |cell| {
+                if solids.as_slice()[cell] {
+                    return (0., 0);
+                }
+                let centre = cell % width + 1 + (cell / width + 1) * (width + 2);
+                let residual = coefficients.neighbor_sum(&self.pressures, cell, centre)
+                    - coefficients.counts[cell] * self.pressures[centre]
+                    - constants.as_slice()[cell];
+                (residual * residual, 1)
+            },
+            |(sum, count): (Float, u32), (other_sum, other_count)| {
+                (sum + other_sum, count + other_count)
+            },
+        );
+        if count == 0 {
+            return 0.;
+        }
+        (sum / count as Float).sqrt()
+    }
+
+    /// Relaxes every cell, or only those with `x + y` of the given parity,
+    /// into the back buffer and swaps it in.
+    fn sweep(&mut self, constants: &CellGrid<Float>, parity: Option<usize>) {
+        let coefficients = &self.coefficients;
+        let pressures = &self.pressures;
+        let [width, height] = coefficients.dimensions.to_array().map(|n| n as usize);
+        let relax_row = |(j, row): (usize, &mut [Float])| {
+            coefficients.relax_row(
+                pressures,
+                constants.as_slice(),
+                j,
+                &mut row[1..=width],
+                parity,
+            )
+        };
+        let rows = &mut self.back[width + 2..(height + 1) * (width + 2)];
+
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+
+            rows.par_chunks_mut(width + 2)
+                .enumerate()
+                .for_each(relax_row);
+        }
+        #[cfg(not(feature = "parallel"))]
+        rows.chunks_mut(width + 2).enumerate().for_each(relax_row);
+
+        mem::swap(&mut self.pressures, &mut self.back);
+        self.coefficients.refresh(&mut self.pressures);
+    }
+}
+
+impl Coefficients {
+    /// Sum of the fluid neighbors of the `cell` at `centre` in the padded
+    /// `pressures`.
+    fn neighbor_sum(&self, pressures: &[Float], cell: usize, centre: usize) -> Float {
+        let padded = self.dimensions.x as usize + 2;
+        [centre + 1, centre - 1, centre + padded, centre - padded]
+            .into_iter()
+            .zip(&self.fluid)
+            .fold(0., |sum, (neighbor, fluid)| {
+                sum + pressures[neighbor] * fluid[cell]
+            })
+    }
+
+    /// New pressures of row `j` from the padded `pressures`, keeping those of
+    /// the cells of the other parity if one is given.
+    fn relax_row(
+        &self,
+        pressures: &[Float],
+        constants: &[Float],
+        j: usize,
+        out: &mut [Float],
+        parity: Option<usize>,
+    ) {
+        let width = self.dimensions.x as usize;
+        let padded = width + 2;
+        let cells = j * width..(j + 1) * width;
+
+        // Slices of exactly `width` entries, so that the bounds checks are
+        // hoisted out of the loop.
+        let row = &pressures[(j + 1) * padded..(j + 2) * padded];
+        let [right, left, centre] = [2, 0, 1].map(|start| &row[start..start + width]);
+        let above = &pressures[(j + 2) * padded + 1..][..width];
+        let below = &pressures[j * padded + 1..][..width];
+        let [fluid_right, fluid_left, fluid_above, fluid_below] =
+            self.fluid.each_ref().map(|fluid| &fluid[cells.clone()]);
+        let counts = &self.counts[cells.clone()];
+        let constants = &constants[cells];
+        let out = &mut out[..width];
+
+        for i in 0..width {
+            // Starting at zero keeps sums of negative zeros positive, as in
+            // the in-place sweep.
+            let sum = 0.
+                + right[i] * fluid_right[i]
+                + left[i] * fluid_left[i]
+                + above[i] * fluid_above[i]
+                + below[i] * fluid_below[i];
+            let relaxed = relax(sum, constants[i], counts[i]);
+            out[i] = match parity {
+                Some(parity) if (i + j) % 2 != parity => centre[i],
+                _ => relaxed,
+            };
+        }
+    }
+
+    /// Sets the ghost cells to the pressures across periodic borders and to
+    /// zero elsewhere, where they are only ever multiplied by zero or stand
+    /// for open sides.
+    fn refresh(&self, pressures: &mut [Float]) {
+        let [width, height] = self.dimensions.to_array().map(|n| n as usize);
+        let padded = width + 2;
+        for row in pressures.chunks_mut(padded).skip(1).take(height) {
+            (row[0], row[width + 1]) = if self.wrap.x {
+                (row[width], row[1])
+            } else {
+                (0., 0.)
+            };
+        }
+        if self.wrap.y {
+            pressures.copy_within(height * padded..(height + 1) * padded, 0);
+            pressures.copy_within(padded..2 * padded, (height + 1) * padded);
+        } else {
+            pressures[..padded].fill(0.);
+            pressures[(height + 1) * padded..].fill(0.);
+        }
+    }
+}
+
+/// The pressure that balances the neighbors' `sum` and the `constant`,
+/// zero without any neighbors.
+fn relax(sum: Float, constant: Float, count: Float) -> Float {
+    if count == 0. {
+        0.
+    } else {
+        (sum - constant) / count
+    }
+}
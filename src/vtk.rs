@@ -0,0 +1,215 @@
+//! Export to VTK XML image data (`.vti`) with a `.pvd` time series index,
+//! as read by ParaView.
+//!
+//! Every step is written as three pieces: the cells with pressure, dye,
+//! solids and the interpolated velocity as cell data, and one image per face
+//! grid with the face velocities as point data, offset to the face centres.
+
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    mem,
+    path::PathBuf,
+};
+
+use glam::UVec2;
+
+use crate::{
+    float::{Float, Vector},
+    grid::Axis,
+    simulation::Simulation,
+};
+
+/// Writes the cells as `.vti`, with pressure, dye, solids and the velocity
+/// interpolated at the cell centres.
+pub fn write_cells(simulation: &Simulation, writer: impl Write) -> io::Result<()> {
+    let velocity = simulation
+        .pressures
+        .indices()
+        .flat_map(|cell| {
+            let position = simulation.pressures.position(cell) * simulation.cell_size;
+            let velocity = simulation.interpolate_velocity(position);
+            [velocity.x, velocity.y, 0.]
+        })
+        .collect::<Vec<_>>();
+    let solid = simulation
+        .solids
+        .iter()
+        .map(|&solid| solid as u8 as Float)
+        .collect::<Vec<_>>();
+
+    write_image(
+        writer,
+        simulation.dimensions + UVec2::ONE,
+        Vector::ZERO,
+        simulation.cell_size,
+        "CellData",
+        &[
+            Array::scalars("pressure", simulation.pressures.as_slice()),
+            Array::scalars("dye", simulation.dye.as_slice()),
+            Array::scalars("solid", &solid),
+            Array::vectors("velocity", &velocity),
+        ],
+    )
+}
+
+/// Writes the velocity component normal to the faces along `axis` as `.vti`
+/// point data, located at the face centres.
+pub fn write_faces(simulation: &Simulation, axis: Axis, writer: impl Write) -> io::Result<()> {
+    let (faces, name) = match axis {
+        Axis::X => (&simulation.velocities_x, "velocity_x"),
+        Axis::Y => (&simulation.velocities_y, "velocity_y"),
+    };
+    write_image(
+        writer,
+        faces.dimensions(),
+        faces.offset() * simulation.cell_size,
+        simulation.cell_size,
+        "PointData",
+        &[Array::scalars(name, faces.as_slice())],
+    )
+}
+
+/// Writes one set of `.vti` files per step into a directory and keeps a
+/// `.pvd` index of all of them up to date.
+pub struct VtkSeries {
+    directory: PathBuf,
+    name: String,
+    steps: Vec<(Float, [String; 3])>,
+}
+
+impl VtkSeries {
+    /// Files are named `{name}_{part}_{step}.vti` and `{name}.pvd`.
+    pub fn new(directory: impl Into<PathBuf>, name: impl Into<String>) -> Self {
+        Self {
+            directory: directory.into(),
+            name: name.into(),
+            steps: Vec::new(),
+        }
+    }
+
+    pub fn pvd_path(&self) -> PathBuf {
+        self.directory.join(format!("{}.pvd", self.name))
+    }
+
+    pub fn write_step(
+        &mut self,
+        simulation: &Simulation,
+        step: u32,
+        time: Float,
+    ) -> io::Result<()> {
+        let file_name = |part: &str| format!("{}_{part}_{step:06}.vti", self.name);
+        let files = [
+            file_name("cells"),
+            file_name("faces_x"),
+            file_name("faces_y"),
+        ];
+
+        write_cells(simulation, self.create(&files[0])?)?;
+        write_faces(simulation, Axis::X, self.create(&files[1])?)?;
+        write_faces(simulation, Axis::Y, self.create(&files[2])?)?;
+
+        self.steps.push((time, files));
+        self.write_pvd()
+    }
+
+    fn create(&self, file_name: &str) -> io::Result<BufWriter<File>> {
+        File::create(self.directory.join(file_name)).map(BufWriter::new)
+    }
+
+    fn write_pvd(&self) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(self.pvd_path())?);
+        writeln!(writer, r#"<?xml version="1.0"?>"#)?;
+        writeln!(writer, r#"<VTKFile type="Collection" version="0.1">"#)?;
+        writeln!(writer, "  <Collection>")?;
+        for (time, files) in &self.steps {
+            for (part, file) in files.iter().enumerate() {
+                writeln!(
+                    writer,
+                    r#"    <DataSet timestep="{time}" part="{part}" file="{}"/>"#,
+                    escape(file),
+                )?;
+            }
+        }
+        writeln!(writer, "  </Collection>")?;
+        writeln!(writer, "</VTKFile>")?;
+        writer.flush()
+    }
+}
+
+struct Array<'a> {
+    name: &'a str,
+    components: usize,
+    values: &'a [Float],
+}
+
+impl<'a> Array<'a> {
+    fn scalars(name: &'a str, values: &'a [Float]) -> Self {
+        Self {
+            name,
+            components: 1,
+            values,
+        }
+    }
+
+    fn vectors(name: &'a str, values: &'a [Float]) -> Self {
+        Self {
+            name,
+            components: 3,
+            values,
+        }
+    }
+}
+
+/// An image of `points` points, with `data` either "CellData" or "PointData".
+fn write_image(
+    mut writer: impl Write,
+    points: UVec2,
+    origin: Vector,
+    spacing: Float,
+    data: &str,
+    arrays: &[Array],
+) -> io::Result<()> {
+    let extent = format!("0 {} 0 {} 0 0", points.x - 1, points.y - 1);
+
+    writeln!(writer, r#"<?xml version="1.0"?>"#)?;
+    writeln!(
+        writer,
+        r#"<VTKFile type="ImageData" version="0.1" byte_order="LittleEndian">"#
+    )?;
+    writeln!(
+        writer,
+        r#"  <ImageData WholeExtent="{extent}" Origin="{} {} 0" Spacing="{spacing} {spacing} {spacing}">"#,
+        origin.x, origin.y,
+    )?;
+    writeln!(writer, r#"    <Piece Extent="{extent}">"#)?;
+    writeln!(writer, "      <{data}>")?;
+    for array in arrays {
+        writeln!(
+            writer,
+            r#"        <DataArray type="Float{}" Name="{}" NumberOfComponents="{}" format="ascii">"#,
+            8 * mem::size_of::<Float>(),
+            array.name,
+            array.components,
+        )?;
+        for values in array.values.chunks(array.components * 8) {
+            write!(writer, "         ")?;
+            for value in values {
+                write!(writer, " {value}")?;
+            }
+            writeln!(writer)?;
+        }
+        writeln!(writer, "        </DataArray>")?;
+    }
+    writeln!(writer, "      </{data}>")?;
+    writeln!(writer, "    </Piece>")?;
+    writeln!(writer, "  </ImageData>")?;
+    writeln!(writer, "</VTKFile>")?;
+    writer.flush()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+}
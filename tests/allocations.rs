@@ -0,0 +1,85 @@
+//! `Simulation::step` reuses its workspace and must not allocate after the
+//! first step. Rayon may allocate for its own bookkeeping, so this only
+//! checks the serial build.
+#![cfg(not(feature = "parallel"))]
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use mac_grid_fluid::{PressureSolver, Scene};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// The only test in this file, so that no other test allocates meanwhile.
+#[test]
+fn step_does_not_allocate() {
+    // Obstacles, open sides, emitters and a moving wall, to go through every
+    // part of the step.
+    let scene: Scene = r#"
+        [grid]
+        dimensions = [24, 16]
+
+        [solver]
+        iterations = 20
+        viscosity = 10.0
+
+        [boundary]
+        left = "open"
+        right = "open"
+
+        [wall_velocity]
+        top = 5.0
+
+        [[obstacles]]
+        shape = "circle"
+        centre = [8.0, 8.0]
+        radius = 3.0
+
+        [[emitters]]
+        region = { shape = "rect", min = [0.0, 4.0], max = [2.0, 12.0] }
+        velocity = [20.0, 0.0]
+        dye = 1.0
+    "#
+    .parse()
+    .unwrap();
+
+    for method in [
+        PressureSolver::Jacobi,
+        PressureSolver::GaussSeidel,
+        PressureSolver::RedBlack,
+    ] {
+        let mut simulation = scene.simulation().unwrap();
+        simulation.solver.method = method;
+        simulation.step();
+
+        let before = ALLOCATIONS.load(Ordering::Relaxed);
+        for _ in 0..3 {
+            simulation.step();
+        }
+        let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+        assert_eq!(allocations, 0, "{method:?} allocated {allocations} times");
+    }
+}
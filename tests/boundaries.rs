@@ -0,0 +1,78 @@
+//! Fluid enters and leaves through open sides.
+
+use glam::uvec2;
+use mac_grid_fluid::{Boundary, Emitter, Float, Region, Scene, Simulation, Vector};
+
+#[test]
+fn inflow_crosses_an_open_side() {
+    let mut simulation = r#"
+        [grid]
+        dimensions = [20, 10]
+        cell_size = 1.0
+
+        [solver]
+        time_step = 0.1
+        iterations = 2000
+        method = "gauss_seidel"
+
+        [boundary]
+        left = "open"
+        right = "open"
+
+        [initial]
+        velocity = [0.0, 0.0]
+
+        [[emitters]]
+        region = { shape = "rect", min = [0.0, 0.0], max = [1.0, 10.0] }
+        velocity = [2.0, 0.0]
+    "#
+    .parse::<Scene>()
+    .unwrap()
+    .simulation()
+    .unwrap();
+
+    for _ in 0..5 {
+        simulation.step();
+    }
+    let flux = |i: u32| {
+        (0..10)
+            .map(|j| simulation.velocities_x[uvec2(i, j)])
+            .sum::<Float>()
+    };
+    // In through the left and out through the right side.
+    assert!(flux(0) > 5., "{}", flux(0));
+    assert!(
+        (flux(20) - flux(0)).abs() < 1e-2,
+        "{} {}",
+        flux(0),
+        flux(20)
+    );
+}
+
+#[test]
+fn periodic_sides_without_a_pair_are_walls() {
+    let walled = || {
+        let mut simulation = Simulation::new(uvec2(12, 8), 1., 0.1).unwrap();
+        simulation.emitters.push(Emitter {
+            region: Region::Circle {
+                centre: Vector::new(3., 4.),
+                radius: 2.,
+            },
+            velocity: Some(Vector::new(-4., 1.)),
+            dye: 1.,
+        });
+        simulation
+    };
+    let mut walls = walled();
+    let mut unpaired = walled();
+    unpaired.boundaries.left = Boundary::Periodic;
+    unpaired.boundaries.top = Boundary::Periodic;
+
+    for _ in 0..5 {
+        walls.step();
+        unpaired.step();
+    }
+    assert_eq!(unpaired.velocities_x, walls.velocities_x);
+    assert_eq!(unpaired.velocities_y, walls.velocities_y);
+    assert_eq!(unpaired.dye, walls.dye);
+}
@@ -0,0 +1,88 @@
+//! Deterministic simulations give bitwise the same fields and diagnostics on
+//! every run, which `Simulation::state_hash` checks step by step.
+
+use glam::uvec2;
+use mac_grid_fluid::{Diagnostics, PressureSolver, Scene, Simulation};
+
+const STEPS: usize = 20;
+
+fn scene() -> Scene {
+    r#"
+        [grid]
+        dimensions = [40, 24]
+
+        [solver]
+        iterations = 30
+        viscosity = 10.0
+        deterministic = true
+
+        [boundary]
+        left = "open"
+        right = "open"
+
+        [wall_velocity]
+        top = 5.0
+
+        [[obstacles]]
+        shape = "circle"
+        centre = [12.0, 12.0]
+        radius = 4.0
+
+        [[emitters]]
+        region = { shape = "rect", min = [0.0, 6.0], max = [2.0, 18.0] }
+        velocity = [20.0, 0.0]
+        dye = 1.0
+    "#
+    .parse()
+    .unwrap()
+}
+
+/// The state hash and diagnostics after every step.
+fn run(mut simulation: Simulation) -> Vec<(u64, Diagnostics)> {
+    (0..STEPS)
+        .map(|_| {
+            simulation.step();
+            (simulation.state_hash(), simulation.diagnostics())
+        })
+        .collect()
+}
+
+#[test]
+fn runs_are_identical_step_by_step() {
+    for method in [
+        PressureSolver::Jacobi,
+        PressureSolver::GaussSeidel,
+        PressureSolver::RedBlack,
+    ] {
+        let mut simulation = scene().simulation().unwrap();
+        simulation.solver.method = method;
+        assert_eq!(run(simulation.clone()), run(simulation), "{method:?}");
+    }
+}
+
+/// Zero and negative zero compare equal but are different bits.
+#[test]
+fn state_hash_sees_the_sign_of_zero() {
+    let mut simulation = scene().simulation().unwrap();
+    let face = uvec2(3, 5);
+    simulation.velocities_y[face] = 0.;
+    let hash = simulation.state_hash();
+
+    simulation.velocities_y[face] = -0.;
+    assert_ne!(simulation.state_hash(), hash);
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn thread_count_does_not_matter() {
+    let runs = [1, 2, 3, 8].map(|threads| {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap()
+            .install(|| run(scene().simulation().unwrap()))
+    });
+    for (threads, other) in [2, 3, 8].into_iter().zip(&runs[1..]) {
+        assert_eq!(other, &runs[0], "{threads} threads");
+    }
+}
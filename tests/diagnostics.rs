@@ -0,0 +1,74 @@
+//! Divergence, CFL number and kinetic energy of known fields.
+
+use glam::uvec2;
+use mac_grid_fluid::{
+    Boundaries, Boundary, Float, PressureSolver, Simulation, SolverOptions, Vector,
+};
+
+#[test]
+fn projection_removes_the_divergence() {
+    let mut simulation = Simulation::new(uvec2(16, 12), 1., 0.1).unwrap();
+    simulation.solver = SolverOptions {
+        method: PressureSolver::GaussSeidel,
+        iterations: 2000,
+    };
+    // A swirl with sources and sinks, no flow through the walls.
+    for faces in [&mut simulation.velocities_x, &mut simulation.velocities_y] {
+        let field = faces.clone();
+        for face in field.indices() {
+            faces[face] = if field.is_boundary(face) {
+                0.
+            } else {
+                ((face.x * 7 + face.y * 13) % 5) as Float - 2.
+            };
+        }
+    }
+    assert!(simulation.divergence().iter().any(|d| d.abs() > 1.));
+
+    simulation.project();
+    let max = simulation
+        .divergence()
+        .iter()
+        .fold(0., |max: Float, d| max.max(d.abs()));
+    assert!(max < 1e-3, "{max}");
+
+    simulation.step();
+    let diagnostics = simulation.diagnostics();
+    assert!(diagnostics.divergence_max < 1e-3, "{diagnostics:?}");
+    assert!(diagnostics.divergence_rms <= diagnostics.divergence_max);
+}
+
+#[test]
+fn cfl_of_a_uniform_flow() {
+    let mut simulation = Simulation::new(uvec2(8, 8), 0.5, 0.1).unwrap();
+    simulation.boundaries = Boundaries {
+        left: Boundary::Periodic,
+        right: Boundary::Periodic,
+        bottom: Boundary::Periodic,
+        top: Boundary::Periodic,
+    };
+    simulation.velocities_x.fill(3.);
+    simulation.velocities_y.fill(-1.);
+    simulation.step();
+
+    let diagnostics = simulation.diagnostics();
+    assert!((diagnostics.max_speed - 3.).abs() < 1e-4, "{diagnostics:?}");
+    // Three per time over half a cell, for a tenth of the time.
+    assert!((diagnostics.cfl - 0.6).abs() < 1e-4, "{diagnostics:?}");
+    assert!(simulation
+        .interpolate_velocity(Vector::splat(2.))
+        .abs_diff_eq(Vector::new(3., -1.), 1e-4));
+}
+
+#[test]
+fn kinetic_energy_of_a_known_field() {
+    let mut simulation = Simulation::new(uvec2(4, 3), 0.5, 0.1).unwrap();
+    simulation.velocities_x.fill(2.);
+    simulation.velocities_y.fill(0.);
+    // Half of 15 faces at speed 2 squared, over cells of a quarter area.
+    assert!((simulation.kinetic_energy() - 7.5).abs() < 1e-5);
+
+    simulation.velocities_y.fill(1.);
+    // Plus half of 16 faces at speed 1.
+    assert!((simulation.kinetic_energy() - 9.5).abs() < 1e-5);
+}
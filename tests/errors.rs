@@ -0,0 +1,134 @@
+//! Bad input is reported as an error rather than a panic.
+
+use glam::{ivec2, uvec2, UVec2};
+use mac_grid_fluid::{
+    Axis, CellGrid, Error, FaceGrid, Float, Scene, SceneError, Simulation, Vector,
+};
+
+#[test]
+fn simulations_need_cells_and_positive_steps() {
+    for dimensions in [uvec2(0, 4), uvec2(4, 0), UVec2::ZERO] {
+        let err = Simulation::new(dimensions, 1., 0.5).unwrap_err();
+        assert!(matches!(err, Error::EmptyDimensions(_)), "{err}");
+    }
+    for (cell_size, time_step) in [
+        (0., 0.5),
+        (1., -0.5),
+        (Float::NAN, 0.5),
+        (1., Float::INFINITY),
+    ] {
+        let err = Simulation::new(uvec2(4, 4), cell_size, time_step).unwrap_err();
+        assert!(matches!(err, Error::NotPositive { .. }), "{err}");
+    }
+
+    assert!(CellGrid::try_from_fn(uvec2(0, 3), |_| 0.).is_err());
+    assert!(FaceGrid::try_from_fn(Axis::Y, uvec2(3, 0), |_| 0.).is_err());
+}
+
+#[test]
+fn scenes_are_validated_before_building() {
+    let mut scene = Scene::default();
+    scene.solver.time_step = -1.;
+    let err = scene.simulation().unwrap_err();
+    assert!(matches!(err, SceneError::Invalid { .. }), "{err}");
+}
+
+#[test]
+fn dye_has_to_be_finite_and_not_negative() {
+    for (dye, key) in [
+        (
+            "[[emitters]]\nregion = { shape = \"circle\", centre = [5.0, 5.0], radius = 2.0 }\ndye = nan",
+            "emitters[0].dye",
+        ),
+        (
+            "[[emitters]]\nregion = { shape = \"circle\", centre = [5.0, 5.0], radius = 2.0 }\ndye = inf",
+            "emitters[0].dye",
+        ),
+        (
+            "[[initial.dye]]\nregion = { shape = \"circle\", centre = [5.0, 5.0], radius = 2.0 }\nvalue = -1.0",
+            "initial.dye[0].value",
+        ),
+        (
+            "[[initial.dye]]\nregion = { shape = \"circle\", centre = [5.0, 5.0], radius = 2.0 }\nvalue = nan",
+            "initial.dye[0].value",
+        ),
+    ] {
+        let err = dye.parse::<Scene>().unwrap_err();
+        assert!(
+            matches!(&err, SceneError::Invalid { key: found, .. } if found == key),
+            "{err}"
+        );
+    }
+}
+
+#[test]
+fn vectors_have_to_be_finite() {
+    for (scene, key) in [
+        ("[initial]\nvelocity = [nan, 0.0]", "initial.velocity"),
+        (
+            "[[emitters]]\nregion = { shape = \"circle\", centre = [5.0, 5.0], radius = 2.0 }\nvelocity = [inf, 0.0]",
+            "emitters[0].velocity",
+        ),
+        (
+            "[[emitters]]\nregion = { shape = \"circle\", centre = [nan, 5.0], radius = 2.0 }",
+            "emitters[0].region.centre",
+        ),
+        (
+            "[[obstacles]]\nshape = \"rect\"\nmin = [nan, 1.0]\nmax = [2.0, 2.0]",
+            "obstacles[0].min",
+        ),
+        (
+            "[[initial.dye]]\nregion = { shape = \"rect\", min = [1.0, 1.0], max = [2.0, inf] }\nvalue = 1.0",
+            "initial.dye[0].region.max",
+        ),
+        (
+            "[[particles.emitters]]\nsource = { shape = \"line\", start = [1.0, 1.0], end = [nan, 2.0] }\nrate = 1.0",
+            "particles.emitters[0].source.end",
+        ),
+    ] {
+        let err = scene.parse::<Scene>().unwrap_err();
+        assert!(
+            matches!(&err, SceneError::Invalid { key: found, .. } if found == key),
+            "{err}"
+        );
+    }
+}
+
+#[test]
+fn checked_accessors_report_the_dimensions() {
+    let mut grid = FaceGrid::new(Axis::X, uvec2(3, 2), 0.);
+    *grid.try_get_mut(ivec2(3, 1)).unwrap() = 1.;
+    assert_eq!(grid.try_get(ivec2(3, 1)).unwrap(), &1.);
+
+    for index in [ivec2(4, 0), ivec2(-1, 0), ivec2(0, 2)] {
+        let err = grid.try_get(index).unwrap_err();
+        assert!(
+            matches!(err, Error::OutOfBounds { dimensions, .. } if dimensions == uvec2(4, 2)),
+            "{err}"
+        );
+    }
+}
+
+/// Positions far outside sample what the border gives, NaN samples NaN.
+#[test]
+fn sampling_anywhere_does_not_panic() {
+    let grid = CellGrid::from_fn(uvec2(5, 4), |cell| (cell.x + 10 * cell.y) as Float);
+    let wrap = glam::BVec2::new(true, false);
+    for position in [
+        Vector::new(-1e30, 2.),
+        Vector::new(1e30, -1e30),
+        Vector::new(Float::INFINITY, 1.),
+        Vector::new(3.5, Float::NEG_INFINITY),
+    ] {
+        let sample = grid.sample(position);
+        let near = grid.sample(position.clamp(Vector::splat(-5.), Vector::splat(10.)));
+        assert_eq!(sample, near, "{position}");
+        let _ = grid.sample_with(position, wrap);
+    }
+    assert_eq!(
+        grid.sample_wrapped(Vector::new(-1e6, 2.5)),
+        grid.sample_wrapped(Vector::new(0., 2.5))
+    );
+    assert!(grid.sample(Vector::new(Float::NAN, 1.)).is_nan());
+    assert!(grid.sample_wrapped(Vector::new(1., Float::NAN)).is_nan());
+}
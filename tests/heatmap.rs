@@ -0,0 +1,82 @@
+//! Colour maps, ranges and the fields they colour.
+
+use glam::{uvec2, vec3, Vec3};
+use mac_grid_fluid::{
+    heatmap::{Colormap, Heatmap, Range, Scalar},
+    Float, Simulation,
+};
+
+fn close(a: Vec3, b: Vec3) -> bool {
+    a.abs_diff_eq(b, 1e-6)
+}
+
+#[test]
+fn colormaps_span_their_stops() {
+    for colormap in Colormap::ALL {
+        assert!(
+            close(colormap.color(-1.), colormap.color(0.)),
+            "{colormap:?}"
+        );
+        assert!(
+            close(colormap.color(2.), colormap.color(1.)),
+            "{colormap:?}"
+        );
+        assert!(
+            close(colormap.color(Float::NAN), colormap.color(0.)),
+            "{colormap:?}"
+        );
+    }
+    assert!(close(
+        Colormap::Viridis.color(0.),
+        vec3(0x44 as f32, 0x01 as f32, 0x54 as f32) / 255.
+    ));
+    assert!(close(
+        Colormap::Viridis.color(1.),
+        vec3(0xfd as f32, 0xe7 as f32, 0x25 as f32) / 255.
+    ));
+    assert!(close(
+        Colormap::Coolwarm.color(0.5),
+        Vec3::splat(0xdd as f32 / 255.)
+    ));
+    assert!(close(Colormap::Grayscale.color(0.25), Vec3::splat(0.25)));
+}
+
+#[test]
+fn ranges_parse_and_widen() {
+    assert_eq!("auto".parse(), Ok(Range::Auto));
+    assert_eq!("-2, 0.5".parse(), Ok(Range::Fixed { min: -2., max: 0.5 }));
+    for invalid in ["", "1", "1,x", "2,1"] {
+        assert!(invalid.parse::<Range>().is_err(), "{invalid:?}");
+    }
+
+    assert_eq!(Range::Auto.limits(&[3., Float::NAN, -1., 2.]), [-1., 3.]);
+    assert_eq!(Range::Auto.limits(&[4., 4.]), [3.5, 4.5]);
+    assert_eq!(Range::Auto.limits(&[]), [0., 1.]);
+    assert_eq!(Range::Fixed { min: 0., max: 2. }.limits(&[5.]), [0., 2.]);
+}
+
+#[test]
+fn heatmap_maps_the_range_to_the_ends() {
+    let mut simulation = Simulation::new(uvec2(4, 3), 1., 0.5).unwrap();
+    simulation.dye[uvec2(1, 2)] = 2.;
+    simulation.dye[uvec2(3, 0)] = -1.;
+
+    let heatmap = Heatmap {
+        scalar: Scalar::Dye,
+        colormap: Colormap::Grayscale,
+        range: Range::Auto,
+    };
+    let (colors, limits) = heatmap.colors(&simulation);
+    assert_eq!(limits, [-1., 2.]);
+    assert!(close(colors[uvec2(1, 2)], Vec3::ONE));
+    assert!(close(colors[uvec2(3, 0)], Vec3::ZERO));
+    assert!(close(colors[uvec2(0, 0)], Vec3::splat(1. / 3.)));
+
+    // Uniform flow of one along both axes.
+    let speed = Scalar::Speed.field(&simulation);
+    assert!(speed
+        .iter()
+        .all(|speed| (speed - Float::sqrt(2.)).abs() < 1e-6));
+    assert_eq!("vorticity".parse(), Ok(Scalar::Vorticity));
+    assert!("velocity".parse::<Scalar>().is_err());
+}
@@ -0,0 +1,156 @@
+//! Invariants of the discretisation on random grids and velocity fields.
+
+use glam::{uvec2, UVec2};
+use mac_grid_fluid::{
+    Boundaries, Boundary, Float, Grid, PressureSolver, Simulation, SolverOptions, Vector,
+};
+use proptest::prelude::*;
+
+const MAX_CELLS: u32 = 12;
+
+/// A simulation with the face velocities taken from `velocities` in turn.
+fn simulation(dimensions: UVec2, cell_size: Float, velocities: &[Float]) -> Simulation {
+    let mut simulation = Simulation::new(dimensions, cell_size, 0.5).unwrap();
+    let mut velocities = velocities.iter().cycle();
+    for velocity in simulation
+        .velocities_x
+        .iter_mut()
+        .chain(simulation.velocities_y.iter_mut())
+    {
+        *velocity = *velocities.next().unwrap();
+    }
+    simulation
+}
+
+fn boundaries(x: u8, y: u8) -> Boundaries {
+    let boundary = |side| match side % 3 {
+        0 => Boundary::Wall,
+        1 => Boundary::Open,
+        _ => Boundary::Periodic,
+    };
+    Boundaries {
+        left: boundary(x),
+        right: boundary(x),
+        bottom: boundary(y),
+        top: boundary(y),
+    }
+}
+
+fn close(a: Float, b: Float) -> bool {
+    (a - b).abs() <= 1e-4 * (1. + a.abs().max(b.abs()))
+}
+
+fn all_close(a: &Grid<Float>, b: &Grid<Float>) -> bool {
+    a.iter().zip(b.iter()).all(|(&a, &b)| close(a, b))
+}
+
+proptest! {
+    #[test]
+    fn step_leaves_no_divergence(
+        width in 1..MAX_CELLS,
+        height in 1..MAX_CELLS,
+        cell_size in 0.1..10. as Float,
+        sides in (0u8..3, 0u8..3),
+        red_black in any::<bool>(),
+        velocities in prop::collection::vec(-1.0..1. as Float, 64),
+    ) {
+        let mut simulation = simulation(uvec2(width, height), cell_size, &velocities);
+        simulation.boundaries = boundaries(sides.0, sides.1);
+        simulation.solver = SolverOptions {
+            method: if red_black {
+                PressureSolver::RedBlack
+            } else {
+                PressureSolver::GaussSeidel
+            },
+            iterations: 1000,
+        };
+        simulation.step();
+
+        for &divergence in simulation.divergence().iter() {
+            prop_assert!(
+                divergence.abs() * cell_size < 1e-4,
+                "divergence {divergence} on {width}x{height} with {:?}",
+                simulation.boundaries,
+            );
+        }
+    }
+
+    #[test]
+    fn interpolation_is_exact_at_faces(
+        width in 1..MAX_CELLS,
+        height in 1..MAX_CELLS,
+        cell_size in 0.1..10. as Float,
+        velocities in prop::collection::vec(-1.0..1. as Float, 64),
+    ) {
+        let simulation = simulation(uvec2(width, height), cell_size, &velocities);
+
+        for (face, &velocity) in simulation.velocities_x.enumerate() {
+            let position = simulation.velocities_x.position(face) * cell_size;
+            let interpolated = simulation.interpolate_velocity(position).x;
+            prop_assert!(close(interpolated, velocity), "{interpolated} at {face}");
+        }
+        for (face, &velocity) in simulation.velocities_y.enumerate() {
+            let position = simulation.velocities_y.position(face) * cell_size;
+            let interpolated = simulation.interpolate_velocity(position).y;
+            prop_assert!(close(interpolated, velocity), "{interpolated} at {face}");
+        }
+    }
+
+    #[test]
+    fn interpolation_is_exact_for_linear_fields(
+        width in 2..MAX_CELLS,
+        height in 2..MAX_CELLS,
+        cell_size in 0.1..10. as Float,
+        coefficients in prop::collection::vec(-1.0..1. as Float, 6),
+        at in (0.0..1. as Float, 0.0..1. as Float),
+    ) {
+        let mut simulation = Simulation::new(uvec2(width, height), cell_size, 0.5).unwrap();
+        let linear = |normalized: Vector| {
+            let [a, b, c, d, e, f] = coefficients[..] else { unreachable!() };
+            Vector::new(
+                a + b * normalized.x + c * normalized.y,
+                d + e * normalized.x + f * normalized.y,
+            )
+        };
+        for face in simulation.velocities_x.indices() {
+            simulation.velocities_x[face] = linear(simulation.velocities_x.position(face)).x;
+        }
+        for face in simulation.velocities_y.indices() {
+            simulation.velocities_y[face] = linear(simulation.velocities_y.position(face)).y;
+        }
+
+        // Away from the border, where both components are interpolated
+        // rather than clamped.
+        let inner = Vector::new(width as Float, height as Float) - 1.;
+        let normalized = Vector::splat(0.5) + Vector::new(at.0, at.1) * inner;
+        let interpolated = simulation.interpolate_velocity(normalized * cell_size);
+        let exact = linear(normalized);
+        prop_assert!(
+            close(interpolated.x, exact.x) && close(interpolated.y, exact.y),
+            "{interpolated} instead of {exact} at {normalized}",
+        );
+    }
+
+    #[test]
+    fn advecting_a_uniform_field_changes_nothing(
+        width in 1..MAX_CELLS,
+        height in 1..MAX_CELLS,
+        cell_size in 0.1..10. as Float,
+        time_step in 0.01..2. as Float,
+        velocity in (-10.0..10. as Float, -10.0..10. as Float),
+        sides in (0u8..3, 0u8..3),
+    ) {
+        let mut simulation = Simulation::new(uvec2(width, height), cell_size, time_step).unwrap();
+        simulation.boundaries = boundaries(sides.0, sides.1);
+        simulation.velocities_x.fill(velocity.0);
+        simulation.velocities_y.fill(velocity.1);
+        simulation.dye.fill(1.);
+        let before = simulation.clone();
+
+        simulation.advect();
+
+        prop_assert!(all_close(&simulation.velocities_x, &before.velocities_x));
+        prop_assert!(all_close(&simulation.velocities_y, &before.velocities_y));
+        prop_assert!(all_close(&simulation.dye, &before.dye));
+    }
+}
@@ -0,0 +1,76 @@
+//! Line integral convolution of still and uniform flows.
+
+use glam::{ivec2, uvec2, Vec3};
+use mac_grid_fluid::{heatmap::Colormap, lic::Lic, Float, Simulation};
+
+fn flow(velocity_x: Float) -> Simulation {
+    let mut simulation = Simulation::new(uvec2(24, 12), 2., 0.1).unwrap();
+    simulation.velocities_x.fill(velocity_x);
+    simulation.velocities_y.fill(0.);
+    simulation
+}
+
+fn variance(values: &[Float]) -> Float {
+    let mean = values.iter().sum::<Float>() / values.len() as Float;
+    values
+        .iter()
+        .map(|value| (value - mean).powi(2))
+        .sum::<Float>()
+        / values.len() as Float
+}
+
+#[test]
+fn still_flow_shows_the_noise() {
+    let simulation = flow(0.);
+    let lic = Lic::new(simulation.dimensions, 2, 4., 7);
+    assert_eq!(lic.dimensions(), uvec2(48, 24));
+
+    let image = lic.image(&simulation);
+    assert_eq!(
+        image,
+        Lic::new(simulation.dimensions, 2, 4., 7).image(&simulation)
+    );
+    assert_ne!(
+        image,
+        Lic::new(simulation.dimensions, 2, 4., 8).image(&simulation)
+    );
+    assert!(image.iter().all(|value| (0. ..1.).contains(value)));
+    assert!(variance(image.as_slice()) > 0.05);
+}
+
+#[test]
+fn uniform_flow_smears_along_rows() {
+    let simulation = flow(1.);
+    let lic = Lic::new(simulation.dimensions, 2, 4., 7);
+    let image = lic.image(&simulation);
+
+    let row = |y: u32| (0..48).map(|x| image[uvec2(x, y)]).collect::<Vec<_>>();
+    let column = |x: u32| (0..24).map(|y| image[uvec2(x, y)]).collect::<Vec<_>>();
+    // Neighbours along a row share most of their streamline, away from the
+    // walls where it is cut short.
+    let steps = row(12)[8..40]
+        .windows(2)
+        .map(|pair| (pair[1] - pair[0]).abs())
+        .fold(0., Float::max);
+    assert!(steps < 0.1, "{steps}");
+    assert!(variance(&row(12)) < variance(&column(24)));
+}
+
+#[test]
+fn solids_are_black() {
+    let mut simulation = flow(1.);
+    *simulation.solids.get_mut(ivec2(5, 5)).unwrap() = true;
+    let lic = Lic::new(simulation.dimensions, 2, 4., 7);
+
+    let image = lic.image(&simulation);
+    let colors = lic.colors(&simulation, Some(Colormap::Viridis));
+    for texel in [uvec2(10, 10), uvec2(11, 11)] {
+        assert_eq!(image[texel], 0.);
+        assert_eq!(colors[texel], Vec3::ZERO);
+    }
+    let gray = lic.colors(&simulation, None);
+    assert!(gray
+        .iter()
+        .all(|color| color.x == color.y && color.y == color.z));
+    assert!(gray.iter().any(|color| *color == Vec3::ONE));
+}
@@ -0,0 +1,181 @@
+//! Lid-driven cavity flow in a unit square with the top wall moving right at
+//! unit speed, run to a steady state and compared against the centre-line
+//! velocities of Ghia, Ghia and Shin, "High-Re solutions for incompressible
+//! flow using the Navier-Stokes equations and a multigrid method", Journal
+//! of Computational Physics 48 (1982), tables I and II.
+
+use glam::uvec2;
+use mac_grid_fluid::{Float, PressureSolver, Simulation, Vector};
+
+/// Heights of the samples along the vertical centre line.
+const GHIA_Y: [Float; 17] = [
+    1.0000, 0.9766, 0.9688, 0.9609, 0.9531, 0.8516, 0.7344, 0.6172, 0.5000, 0.4531, 0.2813, 0.1719,
+    0.1016, 0.0703, 0.0625, 0.0547, 0.0000,
+];
+
+/// Horizontal velocity along the vertical centre line, by Reynolds number.
+const GHIA_U: [(Float, [Float; 17]); 3] = [
+    (
+        100.,
+        [
+            1.00000, 0.84123, 0.78871, 0.73722, 0.68717, 0.23151, 0.00332, -0.13641, -0.20581,
+            -0.21090, -0.15662, -0.10150, -0.06434, -0.04775, -0.04192, -0.03717, 0.00000,
+        ],
+    ),
+    (
+        400.,
+        [
+            1.00000, 0.75837, 0.68439, 0.61756, 0.55892, 0.29093, 0.16256, 0.02135, -0.11477,
+            -0.17119, -0.32726, -0.24299, -0.14612, -0.10338, -0.09266, -0.08186, 0.00000,
+        ],
+    ),
+    (
+        1000.,
+        [
+            1.00000, 0.65928, 0.57492, 0.51117, 0.46604, 0.33304, 0.18719, 0.05702, -0.06080,
+            -0.10648, -0.27805, -0.38289, -0.29730, -0.22220, -0.20196, -0.18109, 0.00000,
+        ],
+    ),
+];
+
+/// Positions of the samples along the horizontal centre line.
+const GHIA_X: [Float; 17] = [
+    1.0000, 0.9688, 0.9609, 0.9531, 0.9453, 0.9063, 0.8594, 0.8047, 0.5000, 0.2344, 0.2266, 0.1563,
+    0.0938, 0.0781, 0.0703, 0.0625, 0.0000,
+];
+
+/// Vertical velocity along the horizontal centre line, by Reynolds number.
+const GHIA_V: [(Float, [Float; 17]); 3] = [
+    (
+        100.,
+        [
+            0.00000, -0.05906, -0.07391, -0.08864, -0.10313, -0.16914, -0.22445, -0.24533, 0.05454,
+            0.17527, 0.17507, 0.16077, 0.12317, 0.10890, 0.10091, 0.09233, 0.00000,
+        ],
+    ),
+    (
+        400.,
+        [
+            0.00000, -0.12146, -0.15663, -0.19254, -0.22847, -0.23827, -0.44993, -0.38598, 0.05186,
+            0.30174, 0.30203, 0.28124, 0.22965, 0.20920, 0.19713, 0.18360, 0.00000,
+        ],
+    ),
+    (
+        1000.,
+        [
+            0.00000, -0.21388, -0.27669, -0.33714, -0.39188, -0.51550, -0.42665, -0.31966, 0.02526,
+            0.32235, 0.33075, 0.37095, 0.32627, 0.30353, 0.29012, 0.27485, 0.00000,
+        ],
+    ),
+];
+
+/// Change of the velocities per unit time below which the flow counts as
+/// steady.
+const STEADY: Float = 1e-3;
+
+fn cavity(resolution: u32, reynolds: Float) -> Simulation {
+    let cell_size = 1. / resolution as Float;
+    let mut simulation =
+        Simulation::new(uvec2(resolution, resolution), cell_size, cell_size).unwrap();
+    simulation.velocities_x.fill(0.);
+    simulation.velocities_y.fill(0.);
+    simulation.wall_velocities.top = 1.;
+    simulation.viscosity = 1. / reynolds;
+    simulation.solver.method = PressureSolver::GaussSeidel;
+    simulation
+}
+
+/// Steps until the largest change of a face velocity per unit time drops
+/// below [`STEADY`], or panics after `max_time`.
+fn run_to_steady_state(simulation: &mut Simulation, max_time: Float) {
+    let mut time = 0.;
+    loop {
+        let before = simulation.clone();
+        simulation.step();
+        time += simulation.time_step;
+
+        let change = before
+            .velocities_x
+            .iter()
+            .zip(simulation.velocities_x.iter())
+            .chain(
+                before
+                    .velocities_y
+                    .iter()
+                    .zip(simulation.velocities_y.iter()),
+            )
+            .fold(0., |max: Float, (before, after)| {
+                max.max((after - before).abs())
+            });
+        if change / simulation.time_step < STEADY {
+            return;
+        }
+        assert!(
+            time < max_time,
+            "not steady after {time}, still changing by {change}"
+        );
+    }
+}
+
+/// Root mean square difference to the reference over the samples at least a
+/// cell away from the walls, where the interpolation extrapolates.
+fn centre_line_error(simulation: &Simulation, reynolds: Float) -> (Float, Float) {
+    let margin = simulation.cell_size;
+    let inside = |t: Float| (margin..=1. - margin).contains(&t);
+    let reference = |table: &[(Float, [Float; 17]); 3]| {
+        table
+            .iter()
+            .find(|(re, _)| *re == reynolds)
+            .map(|(_, values)| *values)
+            .unwrap()
+    };
+    let rms = |errors: Vec<Float>| {
+        (errors.iter().map(|error| error * error).sum::<Float>() / errors.len() as Float).sqrt()
+    };
+
+    let u = reference(&GHIA_U);
+    let u_errors = GHIA_Y
+        .iter()
+        .zip(u)
+        .filter(|(y, _)| inside(**y))
+        .map(|(&y, u)| simulation.interpolate_velocity(Vector::new(0.5, y)).x - u)
+        .collect();
+    let v = reference(&GHIA_V);
+    let v_errors = GHIA_X
+        .iter()
+        .zip(v)
+        .filter(|(x, _)| inside(**x))
+        .map(|(&x, v)| simulation.interpolate_velocity(Vector::new(x, 0.5)).y - v)
+        .collect();
+    (rms(u_errors), rms(v_errors))
+}
+
+fn check(resolution: u32, reynolds: Float, max_time: Float, tolerance: Float) {
+    let mut simulation = cavity(resolution, reynolds);
+    run_to_steady_state(&mut simulation, max_time);
+    let (u_error, v_error) = centre_line_error(&simulation, reynolds);
+    assert!(u_error < tolerance, "u error {u_error} at Re={reynolds}");
+    assert!(v_error < tolerance, "v error {v_error} at Re={reynolds}");
+}
+
+// The numerical diffusion of semi-Lagrangian advection lowers the effective
+// Reynolds number on coarse grids, so the tolerances grow with it.
+
+#[test]
+fn reynolds_100() {
+    check(32, 100., 40., 0.03);
+}
+
+/// Slow, run with `cargo test --release -- --ignored`.
+#[test]
+#[ignore]
+fn reynolds_400() {
+    check(48, 400., 100., 0.1);
+}
+
+/// Slow, run with `cargo test --release -- --ignored`.
+#[test]
+#[ignore]
+fn reynolds_1000() {
+    check(64, 1000., 150., 0.15);
+}
@@ -0,0 +1,87 @@
+//! Streamlines, pathlines and streaklines in a uniform flow to the right.
+
+use glam::{ivec2, uvec2};
+use mac_grid_fluid::{
+    lines::{is_inside, streamline, Pathlines, Streaklines},
+    Float, Simulation, Vector,
+};
+
+const CELL_SIZE: Float = 2.;
+
+fn uniform_flow() -> Simulation {
+    let mut simulation = Simulation::new(uvec2(32, 16), CELL_SIZE, 0.5).unwrap();
+    simulation.velocities_x.fill(1.);
+    simulation.velocities_y.fill(0.);
+    simulation
+}
+
+#[test]
+fn streamlines_are_straight_and_end_at_the_domain() {
+    let simulation = uniform_flow();
+    let seed = Vector::new(16., 8.5) * CELL_SIZE;
+    let line = streamline(&simulation, seed, 0.5, 1000);
+
+    assert!(line.contains(&seed));
+    assert!(line.len() > 2);
+    for pair in line.windows(2) {
+        assert!((pair[1].y - seed.y).abs() < 1e-4, "{pair:?}");
+        assert!(pair[1].x > pair[0].x, "{pair:?}");
+    }
+    assert!(line.iter().all(|&point| is_inside(&simulation, point)));
+    // Steps of half a cell across 32 cells.
+    assert!(line.len() <= 64 + 1);
+
+    assert_eq!(streamline(&simulation, seed, 0.5, 3).len(), 7);
+    assert!(streamline(&simulation, Vector::splat(-1.), 0.5, 10).is_empty());
+}
+
+#[test]
+fn streamlines_stop_at_solids() {
+    let mut simulation = uniform_flow();
+    for y in 0..16 {
+        *simulation.solids.get_mut(ivec2(20, y)).unwrap() = true;
+    }
+    let line = streamline(&simulation, Vector::new(10.5, 8.5) * CELL_SIZE, 0.5, 1000);
+    let last = line.last().unwrap();
+    assert!(
+        last.x < 20. * CELL_SIZE && last.x > 19. * CELL_SIZE,
+        "{last}"
+    );
+}
+
+#[test]
+fn pathlines_follow_the_flow() {
+    let simulation = uniform_flow();
+    let seed = Vector::new(2.5, 8.5) * CELL_SIZE;
+    let mut pathlines = Pathlines::new(&[seed], 5);
+    for _ in 0..10 {
+        pathlines.advance(&simulation);
+    }
+
+    let line = &pathlines.lines[0];
+    assert_eq!(line.len(), 5);
+    for (i, point) in line.iter().enumerate() {
+        let expected = seed + Vector::X * simulation.time_step * i as Float;
+        assert!(point.abs_diff_eq(expected, 1e-4), "{point} {expected}");
+    }
+}
+
+#[test]
+fn streaklines_grow_from_their_seeds_and_lose_what_leaves() {
+    let simulation = uniform_flow();
+    let inside = Vector::new(2.5, 8.5) * CELL_SIZE;
+    let edge = Vector::new(31.9, 8.5) * CELL_SIZE;
+    let mut streaklines = Streaklines::new(&[inside, edge], 4);
+    for _ in 0..10 {
+        streaklines.advance(&simulation);
+    }
+
+    let [line, lost] = &streaklines.lines[..] else {
+        panic!("two streaklines");
+    };
+    assert_eq!(line.len(), 4);
+    assert_eq!(line[0], inside);
+    assert!(line.iter().zip(line.iter().skip(1)).all(|(a, b)| b.x > a.x));
+    assert_eq!(lost.len(), 1);
+    assert_eq!(lost[0], edge);
+}
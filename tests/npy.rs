@@ -0,0 +1,83 @@
+//! Layout of the `.npy` arrays and the `.npz` archives around them.
+
+use std::mem;
+
+use glam::uvec2;
+use mac_grid_fluid::{
+    npy::{fields, write_npy, write_npz},
+    CellGrid, Float, Simulation,
+};
+
+fn u16_at(bytes: &[u8], at: usize) -> usize {
+    u16::from_le_bytes([bytes[at], bytes[at + 1]]) as usize
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> usize {
+    u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap()) as usize
+}
+
+#[test]
+fn arrays_have_a_numpy_header() {
+    let grid = CellGrid::from_fn(uvec2(3, 2), |cell| (cell.x + 10 * cell.y) as Float);
+    let mut bytes = Vec::new();
+    write_npy(&mut bytes, &grid).unwrap();
+
+    assert_eq!(&bytes[..8], b"\x93NUMPY\x01\x00");
+    let header_len = u16_at(&bytes, 8);
+    assert_eq!((10 + header_len) % 64, 0);
+    let header = std::str::from_utf8(&bytes[10..10 + header_len]).unwrap();
+    assert!(header.ends_with('\n'));
+    let descr = if mem::size_of::<Float>() == 8 {
+        "<f8"
+    } else {
+        "<f4"
+    };
+    assert!(header.contains(&format!("'descr': '{descr}'")), "{header}");
+    assert!(header.contains("'fortran_order': False"), "{header}");
+    assert!(header.contains("'shape': (2, 3)"), "{header}");
+
+    let payload = &bytes[10 + header_len..];
+    assert_eq!(payload.len(), 6 * mem::size_of::<Float>());
+    let values = payload
+        .chunks(mem::size_of::<Float>())
+        .map(|chunk| Float::from_le_bytes(chunk.try_into().unwrap()))
+        .collect::<Vec<_>>();
+    assert_eq!(values, [0., 1., 2., 10., 11., 12.]);
+}
+
+#[test]
+fn archives_hold_one_array_per_field() {
+    let simulation = Simulation::new(uvec2(4, 3), 1., 0.1).unwrap();
+    let mut bytes = Vec::new();
+    write_npz(&mut bytes, &fields(&simulation)).unwrap();
+
+    // Walk the local file headers of the stored entries.
+    let mut names = Vec::new();
+    let mut at = 0;
+    while u32_at(&bytes, at) == 0x04034b50 {
+        let size = u32_at(&bytes, at + 18);
+        let name_len = u16_at(&bytes, at + 26);
+        let data = at + 30 + name_len;
+        names.push(String::from_utf8(bytes[at + 30..data].to_vec()).unwrap());
+        assert_eq!(&bytes[data..data + 6], b"\x93NUMPY");
+        at = data + size;
+    }
+    assert_eq!(
+        names,
+        [
+            "pressures.npy",
+            "velocities_x.npy",
+            "velocities_y.npy",
+            "dye.npy",
+            "divergence.npy",
+            "vorticity.npy"
+        ]
+    );
+
+    // The central directory follows and the end record counts the entries.
+    assert_eq!(u32_at(&bytes, at), 0x02014b50);
+    let end = bytes.len() - 22;
+    assert_eq!(u32_at(&bytes, end), 0x06054b50);
+    assert_eq!(u16_at(&bytes, end + 10), 6);
+    assert_eq!(u32_at(&bytes, end + 16), at);
+}
@@ -0,0 +1,164 @@
+//! Emitters, lifetimes, trails and removal of particles.
+
+use glam::{ivec2, uvec2};
+use mac_grid_fluid::{
+    heatmap::Colormap,
+    particles::{ParticleColor, ParticleEmitter, ParticleSettings, Particles, Source},
+    Float, Scene, SceneError, Simulation, Vector,
+};
+
+const CELL_SIZE: Float = 2.;
+
+/// Flows right by a quarter cell per step.
+fn uniform_flow() -> Simulation {
+    let mut simulation = Simulation::new(uvec2(32, 16), CELL_SIZE, 0.5).unwrap();
+    simulation.velocities_x.fill(1.);
+    simulation.velocities_y.fill(0.);
+    simulation
+}
+
+fn emitter(source: Source, rate: Float) -> ParticleEmitter {
+    ParticleEmitter {
+        source,
+        rate,
+        lifetime: None,
+    }
+}
+
+#[test]
+fn sources_spread_over_their_shape() {
+    let line = Source::Line {
+        start: Vector::new(1., 2.),
+        end: Vector::new(1., 6.),
+    };
+    let area = Source::Box {
+        min: Vector::new(2., 3.),
+        max: Vector::new(4., 7.),
+    };
+    let point = Source::Point {
+        position: Vector::new(5., 5.),
+    };
+    let ys = (0..100).map(|i| line.position(i).y).collect::<Vec<_>>();
+    assert!(ys.iter().all(|y| (2. ..=6.).contains(y)));
+    assert!(ys.iter().any(|y| *y < 2.5) && ys.iter().any(|y| *y > 5.5));
+    for i in 0..100 {
+        assert_eq!(line.position(i).x, 1.);
+        let position = area.position(i);
+        assert!(position.cmpge(Vector::new(2., 3.)).all(), "{position}");
+        assert!(position.cmple(Vector::new(4., 7.)).all(), "{position}");
+        assert_eq!(point.position(i), Vector::new(5., 5.));
+    }
+}
+
+#[test]
+fn emitters_release_at_their_rate_and_particles_expire() {
+    let simulation = uniform_flow();
+    let mut particles = Particles::new(ParticleSettings {
+        lifetime: 5.,
+        trail: 3,
+        emitters: vec![emitter(
+            Source::Point {
+                position: Vector::new(2.5, 8.5),
+            },
+            1.,
+        )],
+    });
+
+    // One particle every other step, each living ten steps.
+    for _ in 0..40 {
+        particles.advance(&simulation);
+    }
+    assert_eq!(particles.particles.len(), 5);
+    for particle in &particles.particles {
+        assert!(particle.age < 5.);
+        assert!(particle.trail.len() <= 3);
+        for (newer, older) in particle.trail.iter().zip(particle.trail.iter().skip(1)) {
+            assert!(newer.x > older.x);
+        }
+    }
+    let oldest = &particles.particles[0];
+    assert_eq!(oldest.trail.len(), 3);
+    assert!(oldest.trail[0].x < oldest.position.x);
+
+    let colors = particles.colors(&simulation, ParticleColor::Age, Colormap::Grayscale);
+    assert!(colors.windows(2).all(|pair| pair[0].x > pair[1].x));
+}
+
+#[test]
+fn particles_are_removed_outside_the_fluid() {
+    let mut simulation = uniform_flow();
+    *simulation.solids.get_mut(ivec2(10, 4)).unwrap() = true;
+    let mut particles = Particles::new(ParticleSettings {
+        lifetime: Float::INFINITY,
+        ..ParticleSettings::default()
+    });
+    particles.add(Vector::new(9.5, 4.5) * CELL_SIZE);
+    particles.add(Vector::new(31.5, 8.5) * CELL_SIZE);
+    particles.add(Vector::new(20.5, 8.5) * CELL_SIZE);
+
+    for _ in 0..4 {
+        particles.advance(&simulation);
+    }
+    assert_eq!(particles.particles.len(), 1);
+    let survivor = particles.particles[0].position;
+    assert!(survivor.abs_diff_eq(Vector::new(21.5, 8.5) * CELL_SIZE, 1e-4));
+}
+
+#[test]
+fn scenes_configure_particles() {
+    let scene = r#"
+        [particles]
+        lifetime = 50.0
+        trail = 4
+
+        [[particles.emitters]]
+        source = { shape = "box", min = [1.0, 1.0], max = [3.0, 3.0] }
+        rate = 2.0
+        lifetime = inf
+    "#
+    .parse::<Scene>()
+    .unwrap();
+    assert_eq!(scene.particles.trail, 4);
+    assert_eq!(scene.particles.emitters[0].lifetime, Some(Float::INFINITY));
+
+    let invalid = r#"
+        [[particles.emitters]]
+        source = { shape = "point", position = [1.0, 1.0] }
+        rate = -1.0
+    "#
+    .parse::<Scene>();
+    assert!(matches!(
+        invalid,
+        Err(SceneError::Invalid { key, .. }) if key == "particles.emitters[0].rate"
+    ));
+}
+
+#[test]
+fn reset_particles_start_over() {
+    let simulation = uniform_flow();
+    let settings = ParticleSettings {
+        emitters: vec![emitter(
+            Source::Line {
+                start: Vector::new(2., 2.),
+                end: Vector::new(2., 14.),
+            },
+            1.5,
+        )],
+        ..ParticleSettings::default()
+    };
+    let mut fresh = Particles::new(settings.clone());
+    let mut reset = Particles::new(settings);
+    for _ in 0..7 {
+        reset.advance(&simulation);
+    }
+    reset.add(Vector::new(10., 10.));
+    reset.reset();
+    assert!(reset.particles.is_empty());
+
+    // The emitters pick up at their first position with nothing owed.
+    for _ in 0..5 {
+        fresh.advance(&simulation);
+        reset.advance(&simulation);
+    }
+    assert_eq!(reset, fresh);
+}
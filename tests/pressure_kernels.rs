@@ -0,0 +1,180 @@
+//! The row-oriented pressure sweeps against a straightforward solve that
+//! visits one cell at a time and looks up each of its neighbors.
+
+use glam::{uvec2, IVec2, UVec2};
+use mac_grid_fluid::{
+    Boundaries, Boundary, CellGrid, Float, PressureSolver, Simulation, SolverOptions,
+};
+use proptest::prelude::*;
+
+const MAX_CELLS: u32 = 12;
+
+enum Neighbor {
+    Fluid(UVec2),
+    Solid,
+    Open,
+}
+
+fn neighbor(simulation: &Simulation, normalized: IVec2) -> Neighbor {
+    let dimensions = simulation.dimensions.as_ivec2();
+    let wrapped = IVec2::select(
+        simulation.boundaries.wrap(),
+        normalized.rem_euclid(dimensions),
+        normalized,
+    );
+    if simulation.solids.contains(wrapped) {
+        let cell = wrapped.as_uvec2();
+        if simulation.solids[cell] {
+            Neighbor::Solid
+        } else {
+            Neighbor::Fluid(cell)
+        }
+    } else {
+        match simulation.boundaries.beyond(wrapped, simulation.dimensions) {
+            Boundary::Wall => Neighbor::Solid,
+            Boundary::Open => Neighbor::Open,
+            Boundary::Periodic => unreachable!(),
+        }
+    }
+}
+
+fn relax(
+    simulation: &Simulation,
+    constants: &CellGrid<Float>,
+    pressures: &CellGrid<Float>,
+    cell: UVec2,
+) -> Float {
+    if simulation.solids[cell] {
+        return 0.;
+    }
+    let mut sum = 0.;
+    let mut count = 0;
+    for offset in [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y] {
+        match neighbor(simulation, cell.as_ivec2() + offset) {
+            Neighbor::Fluid(neighbor) => {
+                sum += pressures[neighbor];
+                count += 1;
+            }
+            Neighbor::Open => count += 1,
+            Neighbor::Solid => {}
+        }
+    }
+    if count == 0 {
+        return 0.;
+    }
+    (sum - constants[cell]) / count as Float
+}
+
+/// The pressures `Simulation::project` should arrive at.
+fn reference_pressures(simulation: &Simulation) -> CellGrid<Float> {
+    let constants = CellGrid::from_fn(simulation.dimensions, |cell| {
+        let outflow = if simulation.solids[cell] {
+            0.
+        } else {
+            simulation.velocities_x[cell + UVec2::X] - simulation.velocities_x[cell]
+                + simulation.velocities_y[cell + UVec2::Y]
+                - simulation.velocities_y[cell]
+        };
+        simulation.cell_size / simulation.time_step * outflow
+    });
+    let mut pressures = simulation.pressures.clone();
+    for _ in 0..simulation.solver.iterations {
+        match simulation.solver.method {
+            PressureSolver::Jacobi => {
+                let before = pressures.clone();
+                for cell in before.indices() {
+                    pressures[cell] = relax(simulation, &constants, &before, cell);
+                }
+            }
+            PressureSolver::GaussSeidel => {
+                for cell in simulation.pressures.indices() {
+                    pressures[cell] = relax(simulation, &constants, &pressures, cell);
+                }
+            }
+            PressureSolver::RedBlack => {
+                for parity in [0, 1] {
+                    let before = pressures.clone();
+                    for cell in before.indices() {
+                        if (cell.x + cell.y) % 2 == parity {
+                            pressures[cell] = relax(simulation, &constants, &before, cell);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    pressures
+}
+
+fn boundary(side: u8) -> Boundary {
+    match side % 3 {
+        0 => Boundary::Wall,
+        1 => Boundary::Open,
+        _ => Boundary::Periodic,
+    }
+}
+
+fn close(a: Float, b: Float) -> bool {
+    (a - b).abs() <= 1e-5 * (1. + a.abs().max(b.abs()))
+}
+
+proptest! {
+    #[test]
+    fn sweeps_match_the_per_cell_solve(
+        width in 1..MAX_CELLS,
+        height in 1..MAX_CELLS,
+        sides in (0u8..3, 0u8..3),
+        open in (any::<bool>(), any::<bool>()),
+        method in 0u8..3,
+        iterations in 0u32..20,
+        solids in prop::collection::vec(0u8..5, 144),
+        values in prop::collection::vec(-1.0..1. as Float, 64),
+    ) {
+        let mut simulation = Simulation::new(uvec2(width, height), 0.5, 0.1).unwrap();
+        // Periodic sides come in pairs, walls and open sides mix.
+        let [x, y] = [sides.0, sides.1].map(boundary);
+        let other = |side, open| match side {
+            Boundary::Periodic => Boundary::Periodic,
+            _ if open => Boundary::Open,
+            _ => Boundary::Wall,
+        };
+        simulation.boundaries = Boundaries {
+            left: x,
+            right: other(x, open.0),
+            bottom: y,
+            top: other(y, open.1),
+        };
+        simulation.solver = SolverOptions {
+            method: [
+                PressureSolver::Jacobi,
+                PressureSolver::GaussSeidel,
+                PressureSolver::RedBlack,
+            ][method as usize],
+            iterations,
+        };
+        for (solid, kind) in simulation.solids.iter_mut().zip(&solids) {
+            *solid = *kind == 0;
+        }
+        let mut values = values.iter().cycle();
+        for value in simulation
+            .pressures
+            .iter_mut()
+            .chain(simulation.velocities_x.iter_mut())
+            .chain(simulation.velocities_y.iter_mut())
+        {
+            *value = *values.next().unwrap();
+        }
+        let reference = reference_pressures(&simulation);
+
+        simulation.project();
+
+        for (cell, &pressure) in simulation.pressures.enumerate() {
+            prop_assert!(
+                close(pressure, reference[cell]),
+                "{pressure} instead of {} at {cell} with {:?}",
+                reference[cell],
+                simulation.solver,
+            );
+        }
+    }
+}
@@ -0,0 +1,81 @@
+//! CSV and JSON lines records of a few steps.
+
+use std::io::{self, Write};
+
+use glam::uvec2;
+use mac_grid_fluid::{
+    recorder::{RecordFormat, Recorder},
+    Simulation,
+};
+
+fn record(format: RecordFormat) -> String {
+    let mut simulation = Simulation::new(uvec2(8, 6), 1., 0.1).unwrap();
+    let mut output = Vec::new();
+    {
+        let mut recorder = Recorder::new(&mut output, format).unwrap();
+        for step in 1..=3 {
+            simulation.step();
+            recorder.record(&simulation, step).unwrap();
+        }
+        recorder.flush().unwrap();
+    }
+    String::from_utf8(output).unwrap()
+}
+
+#[test]
+fn csv_has_a_header_and_a_row_per_step() {
+    let csv = record(RecordFormat::Csv);
+    let mut lines = csv.lines();
+    assert_eq!(
+        lines.next(),
+        Some("step,time,energy,enstrophy,divergence,solver_iterations")
+    );
+    let rows = lines.collect::<Vec<_>>();
+    assert_eq!(rows.len(), 3);
+    for (step, row) in (1..).zip(rows) {
+        let columns = row.split(',').collect::<Vec<_>>();
+        assert_eq!(columns.len(), 6, "{row}");
+        assert_eq!(columns[0], step.to_string());
+        assert!(columns[1..]
+            .iter()
+            .all(|column| column.parse::<f64>().is_ok()));
+    }
+}
+
+#[test]
+fn json_lines_are_objects() {
+    let jsonl = record(RecordFormat::JsonLines);
+    let lines = jsonl.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 3);
+    for (step, line) in (1..).zip(lines) {
+        let value: serde_json::Value = serde_json::from_str(line).unwrap();
+        let object = value.as_object().unwrap();
+        assert_eq!(object.len(), 6);
+        assert_eq!(object["step"], step);
+        assert!(object["energy"].is_f64(), "{line}");
+        assert!(object["solver_iterations"].is_u64(), "{line}");
+    }
+}
+
+/// Fails every write.
+struct Broken;
+
+impl Write for Broken {
+    fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+        Err(io::Error::other("broken"))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn write_errors_are_returned() {
+    assert!(Recorder::new(Broken, RecordFormat::Csv).is_err());
+
+    let simulation = Simulation::new(uvec2(4, 4), 1., 0.1).unwrap();
+    let mut recorder = Recorder::new(Broken, RecordFormat::JsonLines).unwrap();
+    let err = recorder.record(&simulation, 0).unwrap_err();
+    assert_eq!(err.to_string(), "broken");
+}
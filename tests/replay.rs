@@ -0,0 +1,111 @@
+//! Recorded viewer inputs replay to the same simulation state.
+
+use mac_grid_fluid::{
+    heatmap::Scalar,
+    input::{Controls, Input, InputError, InputRecorder, Replay, RunMode, VelocityMode},
+    Float, Scene, Simulation, Vector,
+};
+
+fn scene() -> Scene {
+    r#"
+        [grid]
+        dimensions = [30, 20]
+
+        [solver]
+        iterations = 20
+        deterministic = true
+
+        [viewer]
+        interaction_radius = 2.0
+    "#
+    .parse()
+    .unwrap()
+}
+
+/// A session: stepping once, then playing while dragging across the domain.
+fn session() -> Vec<(u64, Input)> {
+    let mut inputs = vec![
+        (0, Input::Step),
+        (3, Input::Show(VelocityMode::Staggered)),
+        (4, Input::Run(RunMode::Play)),
+    ];
+    for frame in 5..25 {
+        let position = Vector::new(20. * frame as Float, 150.5);
+        let velocity = Vector::new(3.25, -0.5);
+        inputs.push((frame, Input::Cursor { position, velocity }));
+    }
+    inputs.extend([
+        (25, Input::Particle(Vector::new(100., 80.))),
+        (26, Input::Run(RunMode::Step)),
+        (27, Input::Heatmap(Some(Scalar::Vorticity))),
+        (28, Input::Heatmap(None)),
+        (30, Input::Step),
+        (30, Input::Quit),
+    ]);
+    inputs
+}
+
+/// Runs the frames up to the last input the way the viewer does, returning
+/// the final state.
+fn run(
+    mut simulation: Simulation,
+    mut frames: impl FnMut(u64) -> Vec<Input>,
+    last: u64,
+) -> Simulation {
+    let mut controls = Controls::new(scene().viewer.interaction_radius);
+    for frame in 0..last {
+        let mut step = false;
+        for input in frames(frame) {
+            controls.apply(&input);
+            step |= input == Input::Step;
+        }
+        controls.advance(&mut simulation, step);
+    }
+    simulation
+}
+
+#[test]
+fn replay_reproduces_the_session() {
+    let initial = scene().simulation().unwrap();
+    let inputs = session();
+
+    let mut recording = Vec::new();
+    let mut recorder = InputRecorder::new(&mut recording, &initial).unwrap();
+    for (frame, input) in &inputs {
+        recorder.record(*frame, input).unwrap();
+    }
+
+    let live = run(
+        initial.clone(),
+        |frame| {
+            inputs
+                .iter()
+                .filter(|(recorded, _)| *recorded == frame)
+                .map(|(_, input)| *input)
+                .collect()
+        },
+        30,
+    );
+
+    let mut replay = Replay::read(recording.as_slice()).unwrap();
+    assert_eq!(replay.initial_hash, initial.state_hash());
+    assert_eq!(replay.last_frame(), Some(30));
+    let all = inputs.iter().map(|(_, input)| *input).collect::<Vec<_>>();
+    assert_eq!(replay.clone().frame(30), all);
+    let replayed = run(initial.clone(), |frame| replay.frame(frame), 30);
+
+    assert_ne!(live.state_hash(), initial.state_hash());
+    assert_eq!(replayed.state_hash(), live.state_hash());
+    assert_eq!(replay.frame(30), [Input::Step, Input::Quit]);
+    assert!(replay.is_finished());
+}
+
+#[test]
+fn invalid_lines_are_reported() {
+    let header = format!("mac_grid_fluid inputs 1 {:016x}\n", 0);
+    let err = Replay::read(format!("{header}0 step\n2 jump\n").as_bytes()).unwrap_err();
+    assert!(matches!(err, InputError::Line { line: 3, .. }), "{err}");
+
+    let err = Replay::read("0 step\n".as_bytes()).unwrap_err();
+    assert!(matches!(err, InputError::Header(_)), "{err}");
+}
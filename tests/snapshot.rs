@@ -0,0 +1,138 @@
+//! Snapshots restore the exact state and reject anything else.
+
+use mac_grid_fluid::{Boundary, Float, Scene, Simulation, SnapshotError};
+
+fn simulation() -> Simulation {
+    let mut simulation = r#"
+        [grid]
+        dimensions = [24, 16]
+
+        [solver]
+        iterations = 20
+        viscosity = 5.0
+        deterministic = true
+
+        [boundary]
+        left = "periodic"
+        right = "periodic"
+        bottom = "open"
+
+        [wall_velocity]
+        top = 3.0
+
+        [[obstacles]]
+        shape = "rect"
+        min = [10.0, 4.0]
+        max = [13.0, 9.0]
+
+        [[emitters]]
+        region = { shape = "circle", centre = [4.0, 8.0], radius = 2.0 }
+        velocity = [10.0, 0.0]
+        dye = 1.0
+
+        [[emitters]]
+        region = { shape = "rect", min = [18.0, 2.0], max = [20.0, 4.0] }
+        dye = 0.5
+    "#
+    .parse::<Scene>()
+    .unwrap()
+    .simulation()
+    .unwrap();
+    for _ in 0..5 {
+        simulation.step();
+    }
+    simulation
+}
+
+fn saved(simulation: &Simulation) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    simulation.save(&mut bytes).unwrap();
+    bytes
+}
+
+#[test]
+fn snapshots_round_trip() {
+    let mut simulation = simulation();
+    let mut loaded = Simulation::load(saved(&simulation).as_slice()).unwrap();
+    assert_eq!(loaded.state_hash(), simulation.state_hash());
+    assert_eq!(loaded.solids, simulation.solids);
+    assert_eq!(loaded.dye, simulation.dye);
+    assert_eq!(loaded.emitters, simulation.emitters);
+    assert_eq!(loaded.boundaries, simulation.boundaries);
+    assert_eq!(loaded.wall_velocities, simulation.wall_velocities);
+    assert_eq!(loaded.viscosity, simulation.viscosity);
+    assert!(loaded.deterministic);
+
+    // Both continue the same way.
+    for _ in 0..3 {
+        simulation.step();
+        loaded.step();
+    }
+    assert_eq!(loaded.state_hash(), simulation.state_hash());
+}
+
+#[test]
+fn snapshots_reject_other_files() {
+    let bytes = saved(&simulation());
+
+    let mut magic = bytes.clone();
+    magic[0] = b'X';
+    assert!(matches!(
+        Simulation::load(magic.as_slice()),
+        Err(SnapshotError::Magic(_))
+    ));
+
+    let mut newer = bytes.clone();
+    newer[4..8].copy_from_slice(&2u32.to_le_bytes());
+    assert!(matches!(
+        Simulation::load(newer.as_slice()),
+        Err(SnapshotError::Version(2))
+    ));
+
+    let truncated = &bytes[..bytes.len() - 1];
+    assert!(matches!(
+        Simulation::load(truncated),
+        Err(SnapshotError::Io(_))
+    ));
+
+    // A huge grid is refused before it is allocated.
+    let mut huge = bytes[..9].to_vec();
+    huge.extend(u32::MAX.to_le_bytes());
+    huge.extend(u32::MAX.to_le_bytes());
+    assert!(matches!(
+        Simulation::load(huge.as_slice()),
+        Err(SnapshotError::Invalid("dimensions"))
+    ));
+}
+
+#[test]
+fn snapshots_reject_settings_that_scenes_reject() {
+    let rejected = |change: fn(&mut Simulation)| {
+        let mut simulation = simulation();
+        change(&mut simulation);
+        match Simulation::load(saved(&simulation).as_slice()) {
+            Err(SnapshotError::Invalid(what)) => what,
+            other => panic!("{other:?}"),
+        }
+    };
+    assert_eq!(
+        rejected(|simulation| simulation.boundaries.right = Boundary::Wall),
+        "boundary"
+    );
+    assert_eq!(
+        rejected(|simulation| simulation.solver.iterations = 0),
+        "solver iterations"
+    );
+    assert_eq!(
+        rejected(|simulation| simulation.viscosity = -1.),
+        "viscosity"
+    );
+    assert_eq!(
+        rejected(|simulation| simulation.viscosity = Float::NAN),
+        "viscosity"
+    );
+    assert_eq!(
+        rejected(|simulation| simulation.wall_velocities.top = Float::INFINITY),
+        "wall velocity"
+    );
+}
@@ -0,0 +1,125 @@
+//! The Taylor-Green vortex `u = sin x cos y`, `v = -cos x sin y` on a
+//! periodic `2π` square is a steady solution of the Euler equations, so any
+//! change of the simulated field is numerical error. It has to stay small
+//! and shrink when the grid is refined at a fixed CFL number. With viscosity
+//! it decays exponentially instead.
+
+use glam::uvec2;
+use mac_grid_fluid::{Boundaries, Float, Simulation, Vector};
+
+const TAU: Float = std::f64::consts::TAU as Float;
+
+const CFL: Float = 0.5;
+const DURATION: Float = 1.;
+
+fn velocity(position: Vector) -> Vector {
+    Vector::new(
+        position.x.sin() * position.y.cos(),
+        -position.x.cos() * position.y.sin(),
+    )
+}
+
+fn taylor_green(resolution: u32) -> Simulation {
+    let cell_size = TAU / resolution as Float;
+    let mut simulation =
+        Simulation::new(uvec2(resolution, resolution), cell_size, CFL * cell_size).unwrap();
+    simulation.boundaries = Boundaries::periodic();
+    for face in simulation.velocities_x.indices() {
+        let position = simulation.velocities_x.position(face) * cell_size;
+        simulation.velocities_x[face] = velocity(position).x;
+    }
+    for face in simulation.velocities_y.indices() {
+        let position = simulation.velocities_y.position(face) * cell_size;
+        simulation.velocities_y[face] = velocity(position).y;
+    }
+    simulation
+}
+
+/// Root mean square error of the face velocities against the analytic
+/// solution, relative to its root mean square.
+fn relative_error(simulation: &Simulation) -> Float {
+    let mut error = 0.;
+    let mut norm = 0.;
+    for (faces, component) in [(&simulation.velocities_x, 0), (&simulation.velocities_y, 1)] {
+        for (face, &value) in faces.enumerate() {
+            let exact = velocity(faces.position(face) * simulation.cell_size)[component];
+            error += (value - exact).powi(2);
+            norm += exact.powi(2);
+        }
+    }
+    (error / norm).sqrt()
+}
+
+/// Runs until [`DURATION`] and returns the relative error.
+fn run(resolution: u32) -> Float {
+    let mut simulation = taylor_green(resolution);
+    let steps = (DURATION / simulation.time_step).round() as u32;
+    for _ in 0..steps {
+        simulation.step();
+    }
+    relative_error(&simulation)
+}
+
+#[test]
+fn initial_field_is_discretely_divergence_free() {
+    let simulation = taylor_green(32);
+    let max = simulation
+        .divergence()
+        .iter()
+        .fold(0., |max: Float, divergence| max.max(divergence.abs()));
+    assert!(max < 1e-5, "divergence {max}");
+}
+
+/// Semi-Lagrangian advection is first order at a fixed CFL number, so the
+/// error is bounded by a multiple of the cell size and halves with it.
+#[test]
+fn converges_at_first_order() {
+    let resolutions = [16, 32, 64];
+    let errors = resolutions.map(run);
+
+    for (resolution, error) in resolutions.into_iter().zip(errors) {
+        let cell_size = TAU / resolution as Float;
+        assert!(
+            error < 0.5 * cell_size,
+            "relative error {error} at {resolution}²"
+        );
+    }
+    for pair in errors.windows(2) {
+        let order = (pair[0] / pair[1]).log2();
+        assert!(order > 0.8, "convergence order {order} for errors {pair:?}");
+    }
+}
+
+/// Energy after [`DURATION`] relative to the initial energy.
+fn energy_ratio(resolution: u32, viscosity: Float) -> (Float, Float) {
+    let mut simulation = taylor_green(resolution);
+    simulation.viscosity = viscosity;
+    let initial = simulation.kinetic_energy();
+    let steps = (DURATION / simulation.time_step).round() as u32;
+    for _ in 0..steps {
+        simulation.step();
+    }
+    let time = steps as Float * simulation.time_step;
+    (simulation.kinetic_energy() / initial, time)
+}
+
+/// With viscosity the vortex keeps its shape and decays as `exp(-2νt)`, its
+/// energy as `exp(-4νt)`. Advection dissipates energy as well, at first
+/// order, so the decay is taken relative to an inviscid run on the same grid.
+/// It has to match within 15% of the cell size.
+#[test]
+fn viscosity_decays_at_the_analytic_rate() {
+    let viscosity = 0.1;
+    for resolution in [32, 64] {
+        let (viscous, time) = energy_ratio(resolution, viscosity);
+        let (inviscid, _) = energy_ratio(resolution, 0.);
+        let exact = (-4. * viscosity * time).exp();
+        let error = (viscous / inviscid / exact - 1.).abs();
+        let cell_size = TAU / resolution as Float;
+        assert!(
+            error < 0.15 * cell_size,
+            "decay {} instead of {exact} at {resolution}²",
+            viscous / inviscid
+        );
+    }
+}
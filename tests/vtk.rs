@@ -0,0 +1,98 @@
+//! The `.vti` images and the `.pvd` index parse back to the grid they were
+//! written from.
+
+use std::{env, fs, mem};
+
+use glam::uvec2;
+use mac_grid_fluid::{
+    vtk::{write_cells, write_faces, VtkSeries},
+    Axis, Float, Simulation,
+};
+
+/// The value of every `name="..."` attribute in `xml`, in order.
+fn attributes<'a>(xml: &'a str, name: &str) -> Vec<&'a str> {
+    let pattern = format!(" {name}=\"");
+    xml.match_indices(&pattern)
+        .map(|(start, _)| {
+            let rest = &xml[start + pattern.len()..];
+            &rest[..rest.find('"').unwrap()]
+        })
+        .collect()
+}
+
+/// Numbers between the opening and closing tag of each data array.
+fn values(xml: &str) -> Vec<Vec<Float>> {
+    xml.split("<DataArray")
+        .skip(1)
+        .map(|array| {
+            let body = &array[array.find('>').unwrap() + 1..array.find("</DataArray>").unwrap()];
+            body.split_whitespace()
+                .map(|value| value.parse().unwrap())
+                .collect()
+        })
+        .collect()
+}
+
+fn simulation() -> Simulation {
+    Simulation::new(uvec2(5, 3), 0.5, 0.1).unwrap()
+}
+
+#[test]
+fn cells_are_written_as_cell_data() {
+    let mut xml = Vec::new();
+    write_cells(&simulation(), &mut xml).unwrap();
+    let xml = String::from_utf8(xml).unwrap();
+
+    assert_eq!(attributes(&xml, "WholeExtent"), ["0 5 0 3 0 0"]);
+    assert_eq!(attributes(&xml, "Spacing"), ["0.5 0.5 0.5"]);
+    assert_eq!(attributes(&xml, "Origin"), ["0 0 0"]);
+    assert!(xml.contains("<CellData>"));
+    assert_eq!(
+        attributes(&xml, "Name"),
+        ["pressure", "dye", "solid", "velocity"]
+    );
+    assert_eq!(attributes(&xml, "NumberOfComponents"), ["1", "1", "1", "3"]);
+    let float = format!("Float{}", 8 * mem::size_of::<Float>());
+    assert!(attributes(&xml, "type").iter().skip(1).all(|t| *t == float));
+
+    let lengths = values(&xml).iter().map(Vec::len).collect::<Vec<_>>();
+    assert_eq!(lengths, [15, 15, 15, 45]);
+}
+
+#[test]
+fn faces_are_written_as_point_data() {
+    let mut xml = Vec::new();
+    write_faces(&simulation(), Axis::X, &mut xml).unwrap();
+    let xml = String::from_utf8(xml).unwrap();
+
+    assert_eq!(attributes(&xml, "WholeExtent"), ["0 5 0 2 0 0"]);
+    assert_eq!(attributes(&xml, "Origin"), ["0 0.25 0"]);
+    assert!(xml.contains("<PointData>"));
+    assert_eq!(attributes(&xml, "Name"), ["velocity_x"]);
+    assert_eq!(attributes(&xml, "NumberOfComponents"), ["1"]);
+    assert_eq!(values(&xml), [vec![1.; 18]]);
+}
+
+#[test]
+fn series_index_every_step() {
+    let directory = env::temp_dir().join(format!("mac_grid_fluid_vtk_{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let mut series = VtkSeries::new(&directory, "run");
+    let simulation = simulation();
+    series.write_step(&simulation, 0, 0.).unwrap();
+    series.write_step(&simulation, 1, 0.5).unwrap();
+
+    let pvd = fs::read_to_string(series.pvd_path()).unwrap();
+    assert_eq!(
+        attributes(&pvd, "timestep"),
+        ["0", "0", "0", "0.5", "0.5", "0.5"]
+    );
+    assert_eq!(attributes(&pvd, "part"), ["0", "1", "2", "0", "1", "2"]);
+    let files = attributes(&pvd, "file");
+    assert_eq!(files[0], "run_cells_000000.vti");
+    assert_eq!(files[5], "run_faces_y_000001.vti");
+    for file in files {
+        assert!(directory.join(file).is_file(), "{file}");
+    }
+    fs::remove_dir_all(&directory).unwrap();
+}